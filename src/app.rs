@@ -7,26 +7,47 @@
 mod utils;
 mod task;
 mod renderer;
+mod keys;
+mod theme;
+mod watch;
+pub mod lock;
+pub mod formats;
+pub mod schema;
+pub mod sync;
+pub mod merge;
+pub mod server;
+#[cfg(unix)]
+pub mod control;
 
 use utils::*;
 use renderer::*;
 use task::Task;
+use keys::{Action, bindings, help_bindings, setting_input_bindings, conflict_bindings, merge_bindings, find_action};
+use theme::{Theme, available_themes, find_theme, is_valid_theme_name, save_theme};
+use watch::{FileWatch, same_tasks, same_archive};
+use schema::{DataFile, InvalidFile};
+use merge::Merge;
+
+use crate::error::{Error, Result};
+use crate::paths::Paths;
 
 use std::io::Write;
-use std::{fs, fs::File};
+use std::fs::File;
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
 
-use chrono::{Utc, DateTime};
+use chrono::{Local, NaiveDate, Utc, DateTime};
 
-use tui::style::Color;
+use tui::style::{Color, Modifier};
 
-use crossterm::event::{self, Event as CEvent, KeyCode};
+use crossterm::event::{self, Event as CEvent, KeyCode, MouseButton, MouseEvent, MouseEventKind};
 
 use tui::{
     backend::Backend,
+    layout::Rect,
     style::Style,
     text::{Spans, Span},
     Terminal,
@@ -34,14 +55,32 @@ use tui::{
 
 use serde::{Deserialize, Serialize};
 
+use uuid::Uuid;
+
 
 // ---- CONSTANTS ----
 const BLINK_TIME: Duration = Duration::from_millis(400);
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+const WATCH_TIME: Duration = Duration::from_secs(1);
+const TOAST_TIME: Duration = Duration::from_secs(5);
 
 
 enum Event<I> {
     Input(I),
+    Mouse(MouseEvent),
+    Resize(u16, u16),
     Tick,
+    Failed(Error),
+    // A command sent through the control socket
+    #[cfg(unix)]
+    Remote(control::Request),
+}
+
+// Short message shown over the bottom of the screen for a while
+struct Toast {
+    message: String,
+    is_error: bool,
+    shown_at: Instant,
 }
 
 #[derive(PartialEq)]
@@ -51,6 +90,13 @@ pub enum PopupType {
     ArchiveTasks,
 }
 
+// Data changed on disk while there were unsaved changes in the app
+#[derive(PartialEq, Copy, Clone)]
+enum Conflict {
+    Data,
+    Settings,
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum AppState {
     Display,
@@ -73,6 +119,10 @@ impl From<AppState> for usize {
 #[derive(PartialEq)]
 enum EditSettingField {
     Split,
+    AutosaveDelay,
+    Checkpoint,
+    CalendarFile,
+    Theme,
     NormalFg,
     NormalBg,
     SelectionFg,
@@ -80,6 +130,18 @@ enum EditSettingField {
     Active,
     Title,
     Border,
+    NormalMod,
+    SelectionMod,
+    ActiveMod,
+    TitleMod,
+    BorderMod,
+}
+
+impl EditSettingField {
+    // Fields that change the colours, which makes them no longer a theme
+    fn is_style(&self) -> bool {
+        !matches!(self, EditSettingField::Split | EditSettingField::AutosaveDelay | EditSettingField::Checkpoint | EditSettingField::CalendarFile | EditSettingField::Theme)
+    }
 }
 
 #[derive(PartialEq)]
@@ -89,11 +151,23 @@ enum EditField {
 }
 
 
+// Only the styles are saved, the colours and modifiers for changing are taken
+// from them on load. Missing fields take the default settings.
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 struct Settings {
     // Layout
     is_horizontal: bool,
 
+    // Saving, in seconds where 0 turns it off
+    autosave_secs: u64,
+    checkpoint_secs: u64,
+    // Keeps a calendar of the tasks next to them, updated on every save
+    calendar_file: bool,
+
+    // Name of the applied theme, empty once the colours are changed by hand
+    theme: String,
+
     // Styles
     default: Style,
     highlight: Style,
@@ -103,28 +177,107 @@ struct Settings {
     border: Style,
 
     // Colours for changing
+    #[serde(skip)]
     normal_fg_colour: Color,
+    #[serde(skip)]
     normal_bg_colour: Color,
+    #[serde(skip)]
     select_fg_colour: Color,
+    #[serde(skip)]
     select_bg_colour: Color,
+    #[serde(skip)]
     active_fg_colour: Color,
+    #[serde(skip)]
     title_fg_colour: Color,
+    #[serde(skip)]
     border_colour: Color,
+
+    // Text modifiers for changing
+    #[serde(skip)]
+    normal_modifiers: Modifier,
+    #[serde(skip)]
+    select_modifiers: Modifier,
+    #[serde(skip)]
+    active_modifiers: Modifier,
+    #[serde(skip)]
+    title_modifiers: Modifier,
+    #[serde(skip)]
+    border_modifiers: Modifier,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings::default_settings()
+    }
 }
 
 impl Settings {
     fn set_colours(&mut self) {
-        self.default          = Style::default().fg(self.normal_fg_colour).bg(self.normal_bg_colour);
-        self.highlight        = Style::default().fg(self.select_fg_colour).bg(self.select_bg_colour);
-        self.active_normal    = Style::default().fg(self.active_fg_colour).bg(self.normal_bg_colour);
-        self.active_highlight = Style::default().fg(self.active_fg_colour).bg(self.select_bg_colour);
-        self.title            = Style::default().fg(self.title_fg_colour).bg(self.normal_bg_colour);
-        self.border           = Style::default().fg(self.border_colour).bg(self.normal_bg_colour);
+        self.default          = Style::default().fg(self.normal_fg_colour).bg(self.normal_bg_colour).add_modifier(self.normal_modifiers);
+        self.highlight        = Style::default().fg(self.select_fg_colour).bg(self.select_bg_colour).add_modifier(self.select_modifiers);
+        self.active_normal    = Style::default().fg(self.active_fg_colour).bg(self.normal_bg_colour).add_modifier(self.active_modifiers);
+        self.active_highlight = Style::default().fg(self.active_fg_colour).bg(self.select_bg_colour).add_modifier(self.active_modifiers | self.select_modifiers);
+        self.title            = Style::default().fg(self.title_fg_colour).bg(self.normal_bg_colour).add_modifier(self.title_modifiers);
+        self.border           = Style::default().fg(self.border_colour).bg(self.normal_bg_colour).add_modifier(self.border_modifiers);
+    }
+
+    // Takes the styles of a theme, the colours for changing are taken from the styles
+    fn apply_theme(&mut self, theme: &Theme) {
+        self.default          = theme.default;
+        self.highlight        = theme.highlight;
+        self.active_normal    = theme.active_normal;
+        self.active_highlight = theme.active_highlight;
+        self.title            = theme.title;
+        self.border           = theme.border;
+
+        self.colours_from_styles();
+        self.theme = theme.name.clone();
+    }
+
+    // Sets the colours and modifiers for changing to the ones of the styles
+    fn colours_from_styles(&mut self) {
+        self.normal_fg_colour = self.default.fg.unwrap_or(Color::Reset);
+        self.normal_bg_colour = self.default.bg.unwrap_or(Color::Reset);
+        self.select_fg_colour = self.highlight.fg.unwrap_or(Color::Reset);
+        self.select_bg_colour = self.highlight.bg.unwrap_or(Color::Reset);
+        self.active_fg_colour = self.active_normal.fg.unwrap_or(Color::Reset);
+        self.title_fg_colour  = self.title.fg.unwrap_or(Color::Reset);
+        self.border_colour    = self.border.fg.unwrap_or(Color::Reset);
+
+        self.normal_modifiers = self.default.add_modifier;
+        self.select_modifiers = self.highlight.add_modifier;
+        self.active_modifiers = self.active_normal.add_modifier;
+        self.title_modifiers  = self.title.add_modifier;
+        self.border_modifiers = self.border.add_modifier;
+    }
+
+    fn to_theme(&self, name: &str) -> Theme {
+        Theme {
+            name: String::from(name),
+
+            default:          self.default,
+            highlight:        self.highlight,
+            active_normal:    self.active_normal,
+            active_highlight: self.active_highlight,
+            title:            self.title,
+            border:           self.border,
+        }
+    }
+
+    fn load(path: &Path) -> Result<Settings> {
+        let mut settings: Settings = schema::load(path, DataFile::Settings)?;
+        settings.colours_from_styles();
+
+        Ok(settings)
     }
 
     pub fn default_settings() -> Settings {
         let mut settings: Settings = Settings {
             is_horizontal: true,
+            autosave_secs: 5,
+            checkpoint_secs: 60,
+            calendar_file: false,
+            theme: String::from("Default"),
 
             default:          Style::default(),
             highlight:        Style::default(),
@@ -140,6 +293,12 @@ impl Settings {
             active_fg_colour: Color::Green,
             title_fg_colour:  Color::Green,
             border_colour:    Color::Green,
+
+            normal_modifiers: Modifier::empty(),
+            select_modifiers: Modifier::empty(),
+            active_modifiers: Modifier::empty(),
+            title_modifiers:  Modifier::empty(),
+            border_modifiers: Modifier::empty(),
         };
 
         settings.set_colours();
@@ -149,14 +308,27 @@ impl Settings {
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 struct ArchiveItem {
     date: DateTime<Utc>,
     tasks: Vec<Task>,
 }
 
+impl Default for ArchiveItem {
+    fn default() -> ArchiveItem {
+        ArchiveItem {
+            date: Utc::now(),
+            tasks: vec![],
+        }
+    }
+}
+
 pub struct App {
     // App state
-    data_path: String,
+    data_dir: PathBuf,
+    config_dir: PathBuf,
+    read_only: bool,
+    git_sync: bool,
     last_event: Instant,
     tasks: Vec<Task>,
     archive: Vec<ArchiveItem>,
@@ -164,13 +336,27 @@ pub struct App {
     state: AppState,
     edit_field: EditField,
     edit_setting: EditSettingField,
+    setting_input: Option<String>,
     show_popup: bool,
     popup_type: PopupType,
+    show_help: bool,
+    help_scroll: u16,
+    // Furthest the help scrolls, from its length when last drawn
+    help_max_scroll: u16,
 
     // Displaying variables
     desc_width_char: u16,
     task_block_height: u16,
     first_task: u16,
+    // First line of the settings page shown, following the selected field
+    first_setting_line: u16,
+    menu_tabs: Vec<Rect>,
+    task_list_area: Rect,
+    editor_area: Rect,
+
+    // Mouse variables
+    last_click: Instant,
+    last_click_task: Option<usize>,
 
     // Editing variables
     first_string: String,
@@ -183,54 +369,92 @@ pub struct App {
 
     // Settings
     settings: Settings,
+
+    // External changes
+    tasks_watch: FileWatch,
+    archive_watch: FileWatch,
+    settings_watch: FileWatch,
+    synced_tasks: Vec<Task>,
+    synced_archive: Vec<ArchiveItem>,
+    synced_settings: String,
+    conflict: Option<Conflict>,
+    merge: Option<Merge>,
+    last_watch: Instant,
+
+    // Notifications
+    toast: Option<Toast>,
+    quit_unsaved: bool,
+
+    // Autosave
+    last_input: Instant,
+    last_save: Instant,
 }
 
 impl App {
-    pub fn new(path_to_folder: &String) -> Result<App, Box<dyn std::error::Error>> {
-        let temp_path_to_db = Path::new(path_to_folder).join("tasks.json");
+    // Data files in the folder that can't be loaded
+    pub fn check_files(paths: &Paths) -> Vec<InvalidFile> {
+        vec![
+            schema::check::<Vec<Task>>(&paths.data_dir.join("tasks.json"), DataFile::Tasks),
+            schema::check::<Vec<ArchiveItem>>(&paths.data_dir.join("archive.json"), DataFile::Archive),
+            schema::check::<Settings>(&paths.config_dir.join("settings.json"), DataFile::Settings),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    pub fn new(paths: &Paths, read_only: bool) -> Result<App> {
+        let temp_path_to_db = paths.data_dir.join("tasks.json");
         let path_to_db = temp_path_to_db.as_path();
-        let temp_path_to_archive = Path::new(&path_to_folder).join("archive.json");
+        let temp_path_to_archive = paths.data_dir.join("archive.json");
         let path_to_archive = temp_path_to_archive.as_path();
-        let temp_path_to_settings = Path::new(&path_to_folder).join("settings.json");
+        let temp_path_to_settings = paths.config_dir.join("settings.json");
         let path_to_settings = temp_path_to_settings.as_path();
 
-        if !path_to_db.exists() {
-            let mut file = File::create(path_to_db)?;
-            file.write_all(b"[]")?;
-        }
-
-        let db_content = fs::read_to_string(path_to_db)?;
-        let mut parsed_tasks: Vec<Task> = serde_json::from_str(&db_content)?;
+        // Missing files are only created when they can be written
+        let mut parsed_tasks: Vec<Task> = if path_to_db.exists() {
+            schema::load(path_to_db, DataFile::Tasks)?
+        } else {
+            if !read_only {
+                let mut file = File::create(path_to_db)?;
+                file.write_all(&schema::to_vec(&Vec::<Task>::new())?)?;
+            }
+            vec![]
+        };
 
         for task in &mut parsed_tasks {
             task.is_selected = false;
         }
 
-        if parsed_tasks.len() > 0 {
+        if !parsed_tasks.is_empty() {
             parsed_tasks[0].is_selected = true;
         }
 
-        if !path_to_archive.exists() {
-            let mut file = File::create(path_to_archive)?;
-            file.write_all(b"[]")?;
-        }
-
-        let archive_content = fs::read_to_string(path_to_archive)?;
-        let archive_items: Vec<ArchiveItem> = serde_json::from_str(&archive_content)?;
+        let archive_items: Vec<ArchiveItem> = if path_to_archive.exists() {
+            schema::load(path_to_archive, DataFile::Archive)?
+        } else {
+            if !read_only {
+                let mut file = File::create(path_to_archive)?;
+                file.write_all(&schema::to_vec(&Vec::<ArchiveItem>::new())?)?;
+            }
+            vec![]
+        };
 
-        let settings: Settings;
-        if path_to_settings.exists() {
-            let settings_content = fs::read_to_string("settings.json")?;
-            settings = serde_json::from_str(&settings_content)?;
+        let settings: Settings = if path_to_settings.exists() {
+            Settings::load(path_to_settings)?
         } else {
-            settings = Settings::default_settings();
-        }
+            Settings::default_settings()
+        };
+        let synced_settings = serde_json::to_string(&settings)?;
 
         Ok(App {
-            data_path: path_to_folder.clone(),
+            data_dir: paths.data_dir.clone(),
+            config_dir: paths.config_dir.clone(),
+            read_only,
+            git_sync: !read_only && sync::is_enabled(&paths.data_dir),
             last_event: Instant::now(),
             tasks: parsed_tasks.to_owned(),
-            archive: if archive_items.len() > 0 {
+            archive: if !archive_items.is_empty() {
                     archive_items.iter().map(|a| {
                     ArchiveItem {
                         date: a.date,
@@ -239,7 +463,7 @@ impl App {
                 } else {
                     vec![]
                 },
-            curr_archive: if archive_items.len() > 0 {
+            curr_archive: if !archive_items.is_empty() {
                 archive_items.len() - 1
             } else {
                 0
@@ -247,12 +471,23 @@ impl App {
             state: AppState::Display,
             edit_field: EditField::Description,
             edit_setting: EditSettingField::Split,
+            setting_input: None,
             show_popup: false,
             popup_type: PopupType::NewTask,
+            show_help: false,
+            help_scroll: 0,
+            help_max_scroll: 0,
 
             desc_width_char: 0,
             task_block_height: 0,
             first_task: 0,
+            first_setting_line: 0,
+            menu_tabs: vec![],
+            task_list_area: Rect::default(),
+            editor_area: Rect::default(),
+
+            last_click: Instant::now(),
+            last_click_task: None,
 
             first_string: String::from(""),
             blink_char: '\t',
@@ -262,15 +497,47 @@ impl App {
             cursor_shown: false,
             last_blink: Instant::now(),
 
-            settings: settings,
+            settings,
+
+            tasks_watch: FileWatch::new(temp_path_to_db.clone()),
+            archive_watch: FileWatch::new(temp_path_to_archive.clone()),
+            settings_watch: FileWatch::new(temp_path_to_settings.clone()),
+            synced_tasks: parsed_tasks,
+            synced_archive: archive_items,
+            synced_settings,
+            conflict: None,
+            merge: None,
+            last_watch: Instant::now(),
+
+            toast: None,
+            quit_unsaved: false,
+
+            last_input: Instant::now(),
+            last_save: Instant::now(),
         })
     }
 
 
-    pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         // SET UP EVENT LOOP
         let (tx, rx) = mpsc::channel();
         let tick_rate = Duration::from_millis(200);
+
+        // Other programs can send commands while the app runs, read-only
+        // instances only show the tasks so they don't listen
+        #[cfg(unix)]
+        let _control = if self.read_only {
+            None
+        } else {
+            match control::listen(&self.data_dir, tx.clone()) {
+                Ok(socket) => Some(socket),
+                Err(err) => {
+                    self.show_error(format!("Could not listen for commands: {}", err));
+                    None
+                },
+            }
+        };
+
         thread::spawn(move || {
             let mut last_tick = Instant::now();
             loop {
@@ -278,16 +545,27 @@ impl App {
                     .checked_sub(last_tick.elapsed())
                     .unwrap_or_else(|| Duration::from_secs(0));
 
-                if event::poll(timeout).expect("Polling should work!") {
-                    if let CEvent::Key(key) = event::read().expect("Should be able to read events!") {
-                        tx.send(Event::Input(key)).expect("Should be able to send events!");
+                // Stop when the terminal fails or the app is gone
+                let event = match event::poll(timeout) {
+                    Ok(true) => match event::read() {
+                        Ok(CEvent::Key(key)) => Some(Event::Input(key)),
+                        Ok(CEvent::Mouse(mouse)) => Some(Event::Mouse(mouse)),
+                        Ok(CEvent::Resize(width, height)) => Some(Event::Resize(width, height)),
+                        Err(err) => Some(Event::Failed(err.into())),
+                    },
+                    Ok(false) => None,
+                    Err(err) => Some(Event::Failed(err.into())),
+                };
+
+                if let Some(event) = event {
+                    let failed = matches!(event, Event::Failed(_));
+                    if tx.send(event).is_err() || failed {
+                        return;
                     }
                 }
 
-                if last_tick.elapsed() >= tick_rate {
-                    if let Ok(_) = tx.send(Event::Tick) {
-                        last_tick = Instant::now();
-                    }
+                if last_tick.elapsed() >= tick_rate && tx.send(Event::Tick).is_ok() {
+                    last_tick = Instant::now();
                 }
             }
         });
@@ -298,144 +576,597 @@ impl App {
 
             self.update_times();
 
-            match self.state {
-                AppState::Display => {
-                    match rx.recv()? {
-                        Event::Input(key) => {
-                            match key.code {
-                                KeyCode::Char('q') => {
-                                    if self.show_popup && self.popup_type == PopupType::ArchiveTasks {
-                                        self.show_popup = false;
-                                    } else {
-                                        self.save_to_db();
-                                        self.save_settings();
-                                        return Ok(())
-                                    }
-                                },
-                                KeyCode::Esc => {
-                                    if self.show_popup && self.popup_type == PopupType::ArchiveTasks {
-                                        self.show_popup = false;
-                                    } else {
-                                        self.save_to_db();
-                                        self.save_settings();
-                                        return Ok(())
-                                    }
-                                },
-                                KeyCode::Char('c') => {
-                                    self.show_popup = true;
-                                    self.popup_type = PopupType::ArchiveTasks;
-                                },
-                                KeyCode::Char('s') => self.save_to_db(),
-                                KeyCode::Char('j') => self.inc_sel_task(),
-                                KeyCode::Char('k') => self.dec_sel_task(),
-                                KeyCode::Char('u') => self.move_task_down(),
-                                KeyCode::Char('i') => self.move_task_up(),
-                                KeyCode::Down => self.inc_sel_task(),
-                                KeyCode::Up => self.dec_sel_task(),
-                                KeyCode::Enter => {
-                                    if self.show_popup && self.popup_type == PopupType::ArchiveTasks {
-                                        self.archive_done_tasks();
-                                        self.show_popup = false;
-                                    } else {
-                                        self.activate_task();
-                                    }
-                                },
-                                KeyCode::Char(' ') => self.do_undo_task(),
-                                KeyCode::Char('a') => self.add_task(),
-                                KeyCode::Char('d') => self.del_task(),
-                                KeyCode::Char('e') => {
-                                    self.show_popup = true;
-                                    self.popup_type = PopupType::EditTask;
-                                    self.enter_edit(EditField::Description);
-                                },
-                                KeyCode::Tab => self.state = AppState::Archived,
-                                KeyCode::BackTab => self.state = AppState::Settings,
-                                _ => {}
-                            }
-                        },
-                        Event::Tick => {},
+            let key = match rx.recv()? {
+                Event::Input(key) => {
+                    self.last_input = Instant::now();
+                    key
+                },
+                Event::Mouse(mouse) => {
+                    self.last_input = Instant::now();
+                    self.handle_mouse(mouse);
+                    continue;
+                },
+                Event::Resize(width, height) => {
+                    terminal.resize(Rect::new(0, 0, width, height))?;
+                    continue;
+                },
+                Event::Failed(err) => return Err(err),
+                #[cfg(unix)]
+                Event::Remote(request) => {
+                    let answer = self.handle_remote(request.command);
+                    let _ = request.reply.send(answer);
+                    continue;
+                },
+                Event::Tick => {
+                    if self.toast.as_ref().is_some_and(|t| t.shown_at.elapsed() >= TOAST_TIME) {
+                        self.toast = None;
                     }
+
+                    if self.last_watch.elapsed() >= WATCH_TIME {
+                        self.check_external_changes();
+                        self.last_watch = Instant::now();
+                    }
+
+                    self.autosave();
+                    continue;
                 },
-                AppState::EditTask => {
-                    match rx.recv()? {
-                        Event::Input(key) => {
-                            match key.code {
-                                KeyCode::Esc => self.enter_display(),
-                                KeyCode::Backspace => self.delete_in_field(),
-                                KeyCode::Enter => self.type_in_field('\n'),
-                                KeyCode::Left => self.dec_cursor(),
-                                KeyCode::Right => self.inc_cursor(),
-                                KeyCode::Up => self.dec_line(),
-                                KeyCode::Down => self.inc_line(),
-                                KeyCode::Char(c) => self.type_in_field(c),
-                                KeyCode::Tab => self.change_field(),
-                                _ => {}
-                            }
-                        },
-                        Event::Tick => {},
+            };
+
+            let table = if self.conflict == Some(Conflict::Data) {
+                merge_bindings()
+            } else if self.conflict.is_some() {
+                conflict_bindings()
+            } else if self.show_help {
+                help_bindings()
+            } else if self.setting_input.is_some() {
+                setting_input_bindings()
+            } else {
+                bindings(self.state)
+            };
+
+            match find_action(table, key.code) {
+                Some(action) => {
+                    if self.handle_action(action) {
+                        return Ok(())
                     }
                 },
-                AppState::Archived => {
-                    match rx.recv()? {
-                        Event::Input(key) => {
-                            match key.code {
-                                KeyCode::Char('q') => {self.save_to_db(); self.save_settings(); return Ok(())},
-                                KeyCode::Esc => {self.save_to_db(); self.save_settings(); return Ok(())},
-                                KeyCode::Char('h') => self.inc_arch_item(),
-                                KeyCode::Char('l') => self.dec_arch_item(),
-                                KeyCode::Left => self.inc_arch_item(),
-                                KeyCode::Right => self.dec_arch_item(),
-                                KeyCode::Char('j') => self.inc_sel_task(),
-                                KeyCode::Char('k') => self.dec_sel_task(),
-                                KeyCode::Char(' ') => self.dearchive_task(),
-                                KeyCode::Down => self.inc_sel_task(),
-                                KeyCode::Up => self.dec_sel_task(),
-                                KeyCode::Tab => self.state = AppState::Settings,
-                                KeyCode::BackTab => self.enter_display(),
-                                _ => {}
-                            }
-                        },
-                        Event::Tick => {},
+                None => {
+                    // Any unbound character is text when editing
+                    let popup_open = self.show_help || self.conflict.is_some();
+                    if let (false, KeyCode::Char(c)) = (popup_open, key.code) {
+                        if let Some(input) = &mut self.setting_input {
+                            input.push(c);
+                        } else if self.state == AppState::EditTask {
+                            self.type_in_field(c);
+                        }
                     }
                 },
-                AppState::Settings => {
-                    match rx.recv()? {
-                        Event::Input(key) => {
-                            match key.code {
-                                KeyCode::Char('q') => {self.save_to_db(); self.save_settings(); return Ok(())},
-                                KeyCode::Esc => {self.save_to_db(); self.save_settings(); return Ok(())},
-                                KeyCode::Char('h') => self.state = AppState::Display,
-                                KeyCode::Char('l') => self.state = AppState::Display,
-                                KeyCode::Up => self.dec_setting_selection(),
-                                KeyCode::Down => self.inc_setting_selection(),
-                                KeyCode::Right => self.inc_setting(),
-                                KeyCode::Left => self.dec_setting(),
-                                KeyCode::Tab => self.enter_display(),
-                                KeyCode::BackTab => self.state = AppState::Archived,
-                                _ => {}
-                            }
+            }
+        }
+    }
+
+    // Performs the action bound to a key, returns true when the app has to quit
+    fn handle_action(&mut self, action: Action) -> bool {
+        if self.read_only && action.is_mutating() {
+            return false;
+        }
+
+        if action != Action::Quit {
+            self.quit_unsaved = false;
+        }
+
+        match action {
+            Action::Quit => {
+                if self.show_popup && self.popup_type == PopupType::ArchiveTasks {
+                    self.show_popup = false;
+                } else {
+                    // Don't overwrite changes made by others without asking
+                    self.check_external_changes();
+                    if self.conflict.is_some() {
+                        return false;
+                    }
+
+                    // If saving fails ask again before dropping the changes
+                    match self.save_to_db().and_then(|_| self.save_settings()) {
+                        Ok(()) => return true,
+                        Err(_) if self.quit_unsaved => return true,
+                        Err(err) => {
+                            self.show_error(format!("Could not save: {}. Press 'q' again to quit anyway", err));
+                            self.quit_unsaved = true;
+                            return false;
                         },
-                        Event::Tick => {},
                     }
-                },
+                }
+            },
+            Action::Save => {
+                // Only saves asked for are committed, autosaves wait for the
+                // next one or for the sync on quit
+                let result = self.save_to_db().and_then(|_| {
+                    if self.git_sync { sync::commit(&self.data_dir) } else { Ok(()) }
+                });
+                match result {
+                    Ok(()) => self.show_message(String::from("Saved")),
+                    Err(err) => self.show_error(format!("Could not save: {}", err)),
+                }
+            },
+            Action::ToggleHelp => {
+                self.show_help = !self.show_help;
+                self.help_scroll = 0;
+            },
+            Action::ShowTasks => self.enter_display(),
+            Action::ShowArchive => self.state = AppState::Archived,
+            Action::ShowSettings => self.state = AppState::Settings,
+
+            Action::NextTask => self.inc_sel_task(),
+            Action::PrevTask => self.dec_sel_task(),
+            Action::MoveTaskUp => self.move_task_up(),
+            Action::MoveTaskDown => self.move_task_down(),
+            Action::AddTask => self.add_task(),
+            Action::EditTask => {
+                self.show_popup = true;
+                self.popup_type = PopupType::EditTask;
+                self.enter_edit(EditField::Description);
+            },
+            Action::DeleteTask => self.del_task(),
+            Action::ToggleDone => self.do_undo_task(),
+            Action::ActivateTask => {
+                if self.show_popup && self.popup_type == PopupType::ArchiveTasks {
+                    self.archive_done_tasks();
+                    self.show_popup = false;
+                } else {
+                    self.activate_task();
+                }
+            },
+            Action::ArchiveDone => {
+                self.show_popup = true;
+                self.popup_type = PopupType::ArchiveTasks;
+            },
+
+            Action::NewerArchive => self.inc_arch_item(),
+            Action::OlderArchive => self.dec_arch_item(),
+            Action::DearchiveTask => self.dearchive_task(),
+
+            Action::NextSetting => self.inc_setting_selection(),
+            Action::PrevSetting => self.dec_setting_selection(),
+            Action::IncSetting => self.inc_setting(),
+            Action::DecSetting => self.dec_setting(),
+            Action::TypeValue => {
+                if self.selected_colour().is_some() {
+                    self.setting_input = Some(String::from(""));
+                }
+            },
+            Action::SaveTheme => {
+                self.edit_setting = EditSettingField::Theme;
+                self.setting_input = Some(String::from(""));
+            },
+            Action::ApplyInput => self.apply_setting_input(),
+            Action::CancelInput => self.setting_input = None,
+            Action::DeleteInputChar => {
+                if let Some(input) = &mut self.setting_input {
+                    input.pop();
+                }
+            },
+
+            Action::StopEditing => self.enter_display(),
+            Action::SwitchField => self.change_field(),
+            Action::DeleteChar => self.delete_in_field(),
+            Action::NewLine => self.type_in_field('\n'),
+            Action::CursorLeft => self.dec_cursor(),
+            Action::CursorRight => self.inc_cursor(),
+            Action::CursorUp => self.dec_line(),
+            Action::CursorDown => self.inc_line(),
+
+            Action::ReloadExternal => {
+                match self.conflict {
+                    Some(Conflict::Data) => self.reload_data(),
+                    Some(Conflict::Settings) => self.reload_settings(),
+                    None => {},
+                }
+                self.conflict = None;
+                self.merge = None;
+            },
+            Action::KeepMine => {
+                let result = match self.conflict {
+                    Some(Conflict::Data) => self.save_to_db(),
+                    Some(Conflict::Settings) => self.save_settings(),
+                    None => Ok(()),
+                };
+                if let Err(err) = result {
+                    self.show_error(format!("Could not save: {}", err));
+                }
+                self.conflict = None;
+                self.merge = None;
+            },
+            Action::UseMine => self.resolve_conflict(false),
+            Action::UseTheirs => self.resolve_conflict(true),
+
+            Action::ScrollHelpDown => self.scroll_help(true),
+            Action::ScrollHelpUp => self.scroll_help(false),
+        }
+
+        false
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.click(mouse.column, mouse.row),
+            MouseEventKind::ScrollDown => {
+                if self.show_help {
+                    self.scroll_help(true);
+                } else {
+                    self.scroll_tasks(true);
+                }
+            },
+            MouseEventKind::ScrollUp => {
+                if self.show_help {
+                    self.scroll_help(false);
+                } else {
+                    self.scroll_tasks(false);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn click(&mut self, x: u16, y: u16) {
+        if self.show_help || self.conflict.is_some() {
+            return;
+        }
+
+        if self.state == AppState::EditTask {
+            if self.show_popup && rect_contains(self.editor_area, x, y) {
+                self.click_editor(x - self.editor_area.x, y - self.editor_area.y);
             }
+            return;
+        }
+
+        // Nothing behind the confirmation popup is clickable
+        if self.show_popup {
+            return;
+        }
+
+        if let Some(tab) = self.menu_tabs.iter().position(|r| rect_contains(*r, x, y)) {
+            match tab {
+                0 => self.enter_display(),
+                1 => self.state = AppState::Archived,
+                _ => self.state = AppState::Settings,
+            }
+            return;
+        }
+
+        if rect_contains(self.task_list_area, x, y) {
+            let index = self.first_task as usize + (y - self.task_list_area.y) as usize;
+            if !self.select_task(index) {
+                return;
+            }
+
+            if self.state == AppState::Display && !self.read_only {
+                if x < self.task_list_area.x + CHECKBOX_WIDTH {
+                    self.do_undo_task();
+                } else if self.last_click_task == Some(index) && self.last_click.elapsed() < DOUBLE_CLICK_TIME {
+                    self.activate_task();
+                    self.last_click_task = None;
+                    return;
+                }
+            }
+
+            self.last_click = Instant::now();
+            self.last_click_task = Some(index);
         }
     }
 
-    pub fn save_to_db(&mut self) {
-        let mut full_path = self.data_path.clone();
-        full_path.push_str("tasks.json");
-        fs::write(full_path, &serde_json::to_vec_pretty(&self.tasks).expect("DB should be writeable")).expect("DB should be writeable");
+    fn click_editor(&mut self, col: u16, row: u16) {
+        let field = if row == EDITOR_TITLE_LINE {
+            EditField::Title
+        } else if row >= EDITOR_DESC_LINE {
+            EditField::Description
+        } else {
+            return;
+        };
+
+        if field != self.edit_field {
+            self.change_field();
+        }
 
-        let mut arch_path = self.data_path.clone();
-        arch_path.push_str("archive.json");
-        fs::write(arch_path, &serde_json::to_vec_pretty(&self.archive).expect("Archive should be writeable")).expect("Archive should be writeable");
+        if field == EditField::Title {
+            self.set_cursor_pos(col, 0);
+        } else {
+            self.set_cursor_pos(col, row - EDITOR_DESC_LINE);
+        }
+
+        self.cursor_shown = true;
+        self.last_blink = Instant::now();
     }
 
-    pub fn save_settings(&mut self) {
-        let mut full_path = self.data_path.clone();
-        full_path.push_str("settings.json");
-        fs::write(full_path, &serde_json::to_vec_pretty(&self.settings).expect("Settings should be writeable")).expect("Settings should be writeable");
+    // Returns the list of tasks shown in the current state
+    fn curr_tasks(&mut self) -> Option<&mut Vec<Task>> {
+        match self.state {
+            AppState::Display => Some(&mut self.tasks),
+            AppState::Archived => self.archive.get_mut(self.curr_archive).map(|a| &mut a.tasks),
+            _ => None,
+        }
+    }
+
+    fn select_task(&mut self, index: usize) -> bool {
+        if let Some(tasks) = self.curr_tasks() {
+            if index < tasks.len() {
+                for (task_index, task) in tasks.iter_mut().enumerate() {
+                    task.is_selected = task_index == index;
+                }
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // Keeps the first shown task valid for the current list and its height
+    fn fit_first_task(&mut self) {
+        let height = self.task_block_height as usize;
+        let (num_tasks, selected) = match self.curr_tasks() {
+            Some(tasks) => (tasks.len(), tasks.iter().position(|t| t.is_selected)),
+            None => return,
+        };
+
+        let mut first = self.first_task as usize;
+        if let Some(selected) = selected {
+            if selected < first {
+                first = selected;
+            } else if height > 0 && selected >= first + height {
+                first = selected + 1 - height;
+            }
+        }
+
+        if first + height > num_tasks {
+            first = num_tasks.saturating_sub(height);
+        }
+
+        self.first_task = first as u16;
+    }
+
+    fn scroll_help(&mut self, down: bool) {
+        self.help_scroll = if down {
+            (self.help_scroll + 1).min(self.help_max_scroll)
+        } else {
+            self.help_scroll.saturating_sub(1)
+        };
+    }
+
+    fn scroll_tasks(&mut self, down: bool) {
+        let height = self.task_block_height as usize;
+        let first = self.first_task as usize;
+        let (num_tasks, selected) = match self.curr_tasks() {
+            Some(tasks) => (tasks.len(), tasks.iter().position(|t| t.is_selected)),
+            None => return,
+        };
+
+        if num_tasks <= height || height == 0 {
+            return;
+        }
+
+        if down && first + height < num_tasks {
+            self.first_task += 1;
+        } else if !down && first > 0 {
+            self.first_task -= 1;
+        }
+
+        // Keep the selection within the visible tasks
+        let first = self.first_task as usize;
+        if let Some(selected) = selected {
+            if selected < first {
+                self.select_task(first);
+            } else if selected >= first + height {
+                self.select_task(first + height - 1);
+            }
+        }
+    }
+
+    fn data_file(&self, name: &str) -> PathBuf {
+        self.data_dir.join(name)
+    }
+
+    fn config_file(&self, name: &str) -> PathBuf {
+        self.config_dir.join(name)
+    }
+
+    pub fn save_to_db(&mut self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+
+        schema::save(&self.data_file("tasks.json"), &self.tasks, DataFile::Tasks)?;
+        schema::save(&self.data_file("archive.json"), &self.archive, DataFile::Archive)?;
+        if self.settings.calendar_file {
+            formats::save_calendar(&self.data_dir, &self.tasks, &self.archive)?;
+        }
+
+        self.tasks_watch.stamp();
+        self.archive_watch.stamp();
+        self.synced_tasks = self.tasks.clone();
+        self.synced_archive = self.archive.clone();
+        self.last_save = Instant::now();
+
+        Ok(())
+    }
+
+    pub fn save_settings(&mut self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+
+        schema::save(&self.config_file("settings.json"), &self.settings, DataFile::Settings)?;
+
+        self.settings_watch.stamp();
+        self.synced_settings = serde_json::to_string(&self.settings)?;
+
+        Ok(())
+    }
+
+    fn show_message(&mut self, message: String) {
+        self.toast = Some(Toast { message, is_error: false, shown_at: Instant::now() });
+    }
+
+    fn show_error(&mut self, message: String) {
+        self.toast = Some(Toast { message, is_error: true, shown_at: Instant::now() });
+    }
+
+    // Looks for changes made to the data files by other programs. They are
+    // reloaded straight away unless they clash with unsaved changes made here,
+    // then the tasks are merged and the user is asked about the fields changed
+    // on both sides, or which version of the settings to keep.
+    fn check_external_changes(&mut self) {
+        if self.conflict.is_some() || self.state == AppState::EditTask {
+            return;
+        }
+
+        if self.tasks_watch.changed() || self.archive_watch.changed() {
+            if !same_tasks(&self.tasks, &self.synced_tasks) || !same_archive(&self.archive, &self.synced_archive) {
+                self.merge_external();
+                if self.conflict.is_some() {
+                    return;
+                }
+            } else {
+                self.reload_data();
+            }
+        }
+
+        if self.settings_watch.changed() {
+            if serde_json::to_string(&self.settings).ok().as_deref() != Some(self.synced_settings.as_str()) {
+                self.conflict = Some(Conflict::Settings);
+                return;
+            }
+            self.reload_settings();
+        }
+    }
+
+    // Replaces tasks and archive with the files on disk. Files that can't be
+    // parsed, e.g. while they are still being written, are left for the next check.
+    fn reload_data(&mut self) {
+        let disk_tasks: Vec<Task> = match schema::load(&self.data_file("tasks.json"), DataFile::Tasks).ok() {
+            Some(tasks) => tasks,
+            None => return,
+        };
+        let disk_archive: Vec<ArchiveItem> = match schema::load(&self.data_file("archive.json"), DataFile::Archive).ok() {
+            Some(archive) => archive,
+            None => return,
+        };
+
+        // Keep the selection, the running timer and the time tracked since the last save
+        let selected = self.tasks.iter().find(|t| t.is_selected).map(|t| t.id);
+        let running = self.tasks.iter().find(|t| t.is_active).map(|t| t.id);
+        let mut tasks = disk_tasks.clone();
+        for task in &mut tasks {
+            task.is_selected = Some(task.id) == selected;
+            if running.is_some() {
+                task.is_active = Some(task.id) == running;
+            }
+
+            let mine = self.tasks.iter().find(|t| t.id == task.id).map(|t| t.elapsed_time);
+            let synced = self.synced_tasks.iter().find(|t| t.id == task.id).map(|t| t.elapsed_time);
+            if let (Some(mine), Some(synced)) = (mine, synced) {
+                if mine > synced {
+                    task.add_time(today(), mine - synced);
+                }
+            }
+        }
+
+        if !tasks.iter().any(|t| t.is_selected) {
+            if let Some(first) = tasks.first_mut() {
+                first.is_selected = true;
+            }
+        }
+
+        self.tasks = tasks;
+        self.archive = disk_archive.clone();
+        if self.curr_archive >= self.archive.len() {
+            self.curr_archive = self.archive.len().saturating_sub(1);
+        }
+
+        self.synced_tasks = disk_tasks;
+        self.synced_archive = disk_archive;
+        self.tasks_watch.stamp();
+        self.archive_watch.stamp();
+
+        self.show_message(String::from("Reloaded tasks changed on disk"));
+    }
+
+    // Merges the files on disk with the changes made here, both coming from the
+    // tasks as they were last saved or loaded. Fields changed on both sides are
+    // asked about, the rest is saved straight away.
+    fn merge_external(&mut self) {
+        let disk_tasks: Vec<Task> = match schema::load(&self.data_file("tasks.json"), DataFile::Tasks).ok() {
+            Some(tasks) => tasks,
+            None => return,
+        };
+        let disk_archive: Vec<ArchiveItem> = match schema::load(&self.data_file("archive.json"), DataFile::Archive).ok() {
+            Some(archive) => archive,
+            None => return,
+        };
+
+        let merge = merge::merge(
+            (&self.synced_tasks, &self.synced_archive),
+            (&self.tasks, &self.archive),
+            (&disk_tasks, &disk_archive),
+        );
+
+        if merge.conflicts().is_empty() {
+            self.apply_merge(merge);
+        } else {
+            self.merge = Some(merge);
+            self.conflict = Some(Conflict::Data);
+        }
+    }
+
+    // Takes a side for the conflict shown, the merge is saved after the last one
+    fn resolve_conflict(&mut self, use_theirs: bool) {
+        let Some(merge) = &mut self.merge else {
+            return;
+        };
+
+        merge.resolve(use_theirs);
+        if merge.current().is_none() {
+            if let Some(merge) = self.merge.take() {
+                self.apply_merge(merge);
+            }
+            self.conflict = None;
+        }
+    }
+
+    fn apply_merge(&mut self, merge: Merge) {
+        let (mut tasks, archive) = merge.finish();
+
+        // Only this app's selection and timer count
+        let selected = self.tasks.iter().find(|t| t.is_selected).map(|t| t.id);
+        let running = self.tasks.iter().find(|t| t.is_active).map(|t| t.id);
+        for task in &mut tasks {
+            task.is_selected = Some(task.id) == selected;
+            task.is_active = Some(task.id) == running;
+        }
+
+        if !tasks.iter().any(|t| t.is_selected) {
+            if let Some(first) = tasks.first_mut() {
+                first.is_selected = true;
+            }
+        }
+
+        self.tasks = tasks;
+        self.archive = archive;
+        if self.curr_archive >= self.archive.len() {
+            self.curr_archive = self.archive.len().saturating_sub(1);
+        }
+
+        match self.save_to_db() {
+            Ok(()) => self.show_message(String::from("Merged tasks changed on disk")),
+            Err(err) => self.show_error(format!("Could not save: {}", err)),
+        }
+    }
+
+    fn reload_settings(&mut self) {
+        if let Ok(settings) = Settings::load(&self.config_file("settings.json")) {
+            self.synced_settings = serde_json::to_string(&settings).unwrap_or_default();
+            self.settings = settings;
+            self.settings_watch.stamp();
+        }
     }
 
     pub fn move_task_up(&mut self) {
@@ -474,40 +1205,34 @@ impl App {
         let mut index = 0;
 
         match self.state {
-            AppState::Display => {
-                if self.tasks.len() > 0 {
-                    while index < self.tasks.len() - 1 {
-                        if self.tasks[index].is_selected {
-                            self.tasks[index].is_selected = false;
-                            self.tasks[index + 1].is_selected = true;
-
-                            if (index + 1) as u16 >= self.first_task + self.task_block_height {
-                                self.first_task = (index + 1) as u16 - (self.task_block_height - 1);
-                            }
-                            break;
-                        }
+            AppState::Display if !self.tasks.is_empty() => {
+                while index < self.tasks.len() - 1 {
+                    if self.tasks[index].is_selected {
+                        self.tasks[index].is_selected = false;
+                        self.tasks[index + 1].is_selected = true;
 
-                        index += 1;
+                        if (index + 1) as u16 >= self.first_task + self.task_block_height {
+                            self.first_task = (index + 1) as u16 - self.task_block_height.saturating_sub(1);
+                        }
+                        break;
                     }
+
+                    index += 1;
                 }
             },
-            AppState::Archived => {
-                if self.archive.len() > 0 {
-                    if self.archive[self.curr_archive].tasks.len() > 0 {
-                        while index < self.archive[self.curr_archive].tasks.len() - 1 {
-                            if self.archive[self.curr_archive].tasks[index].is_selected {
-                                self.archive[self.curr_archive].tasks[index].is_selected = false;
-                                self.archive[self.curr_archive].tasks[index + 1].is_selected = true;
-
-                                if (index + 1) as u16 >= self.first_task + self.task_block_height {
-                                    self.first_task = (index + 1) as u16 - (self.task_block_height - 1);
-                                }
-                                break;
-                            }
-
-                            index += 1;
+            AppState::Archived if !self.archive.is_empty() && !self.archive[self.curr_archive].tasks.is_empty() => {
+                while index < self.archive[self.curr_archive].tasks.len() - 1 {
+                    if self.archive[self.curr_archive].tasks[index].is_selected {
+                        self.archive[self.curr_archive].tasks[index].is_selected = false;
+                        self.archive[self.curr_archive].tasks[index + 1].is_selected = true;
+
+                        if (index + 1) as u16 >= self.first_task + self.task_block_height {
+                            self.first_task = (index + 1) as u16 - self.task_block_height.saturating_sub(1);
                         }
+                        break;
                     }
+
+                    index += 1;
                 }
             },
             _ => {}
@@ -532,20 +1257,18 @@ impl App {
                     index += 1;
                 }
             },
-            AppState::Archived => {
-                if self.archive.len() > 0 {
-                    while index < self.archive[self.curr_archive].tasks.len() {
-                        if self.archive[self.curr_archive].tasks[index].is_selected {
-                            self.archive[self.curr_archive].tasks[index].is_selected = false;
-                            self.archive[self.curr_archive].tasks[index - 1].is_selected = true;
+            AppState::Archived if !self.archive.is_empty() => {
+                while index < self.archive[self.curr_archive].tasks.len() {
+                    if self.archive[self.curr_archive].tasks[index].is_selected {
+                        self.archive[self.curr_archive].tasks[index].is_selected = false;
+                        self.archive[self.curr_archive].tasks[index - 1].is_selected = true;
 
-                            if ((index - 1) as u16) < self.first_task {
-                                self.first_task = (index - 1) as u16;
-                            }
+                        if ((index - 1) as u16) < self.first_task {
+                            self.first_task = (index - 1) as u16;
                         }
-
-                        index += 1;
                     }
+
+                    index += 1;
                 }
             },
             _ => {}
@@ -606,7 +1329,7 @@ impl App {
 
         self.state = AppState::Display;
 
-        if !any_selected && self.tasks.len() > 0 {
+        if !any_selected && !self.tasks.is_empty() {
             self.tasks[0].is_selected = true;
         }
     }
@@ -653,20 +1376,149 @@ impl App {
     }
 
     fn update_times(&mut self) {
+        // Always restart the count, so idle time isn't added when a timer starts
+        let elapsed = self.last_event.elapsed();
+        self.last_event = Instant::now();
+
+        let today = today();
         for task in &mut self.tasks {
             if task.is_active {
-                task.elapsed_time += self.last_event.elapsed();
-                self.last_event = Instant::now();
+                task.add_time(today, elapsed);
             }
         }
     }
 
+    // Whether there are changes that haven't been written to disk
+    fn is_dirty(&self) -> bool {
+        self.has_edits() || self.tasks.iter().zip(self.synced_tasks.iter()).any(|(a, b)| a.elapsed_time != b.elapsed_time)
+    }
+
+    // Whether tasks or settings were changed since the last save, timers aside
+    fn has_edits(&self) -> bool {
+        !same_tasks(&self.tasks, &self.synced_tasks)
+            || !same_archive(&self.archive, &self.synced_archive)
+            || serde_json::to_string(&self.settings).ok().as_deref() != Some(self.synced_settings.as_str())
+    }
+
+    // Saves once the user has stopped making changes for a while, and every so
+    // often while a timer runs so a crash doesn't lose the tracked time
+    fn autosave(&mut self) {
+        if self.read_only || self.state == AppState::EditTask || self.setting_input.is_some() || self.conflict.is_some() {
+            return;
+        }
+
+        let autosave = self.settings.autosave_secs > 0
+            && self.last_input.elapsed() >= Duration::from_secs(self.settings.autosave_secs)
+            && self.has_edits();
+        let checkpoint = self.settings.checkpoint_secs > 0
+            && self.last_save.elapsed() >= Duration::from_secs(self.settings.checkpoint_secs)
+            && self.tasks.iter().any(|t| t.is_active);
+
+        if !autosave && !checkpoint {
+            return;
+        }
+
+        // Changes made on disk come first
+        self.check_external_changes();
+        if self.conflict.is_some() {
+            return;
+        }
+
+        if let Err(err) = self.save_to_db().and_then(|_| self.save_settings()) {
+            self.show_error(format!("Could not autosave: {}", err));
+            // Wait for the next round instead of trying on every tick
+            self.last_input = Instant::now();
+            self.last_save = Instant::now();
+        }
+    }
+
+    // Runs a command sent through the control socket, returns the answer
+    #[cfg(unix)]
+    fn handle_remote(&mut self, command: control::Command) -> String {
+        use control::Command;
+
+        if let Command::Status = command {
+            return self.status();
+        }
+
+        // Changes would get mixed up with the ones being made here
+        if self.state == AppState::EditTask || self.conflict.is_some() {
+            return String::from("error: the app is busy, finish editing first");
+        }
+        if self.read_only {
+            return String::from("error: the app is read-only");
+        }
+
+        let selected = self.tasks.iter().position(|t| t.is_selected);
+        match command {
+            Command::ToggleActive => match selected {
+                Some(index) if self.tasks[index].is_done && !self.tasks[index].is_active => {
+                    return String::from("error: the selected task is done");
+                },
+                Some(_) => {
+                    // The time so far goes to the task that was running
+                    self.update_times();
+                    self.activate_task();
+                },
+                None => return String::from("error: there are no tasks"),
+            },
+            Command::Add(title) => {
+                let task = Task {
+                    title,
+                    is_selected: self.tasks.is_empty(),
+                    ..Task::default()
+                };
+                self.tasks.push(task);
+            },
+            Command::DoneSelected => match selected {
+                Some(index) => {
+                    // A running timer gets its time before it stops
+                    self.update_times();
+                    self.tasks[index].is_done = true;
+                    self.tasks[index].is_active = false;
+                },
+                None => return String::from("error: there are no tasks"),
+            },
+            Command::Status => {},
+        }
+
+        // Saved like changes made with the keys
+        self.last_input = Instant::now();
+
+        String::from("ok")
+    }
+
+    // The running task and the time tracked today, as JSON on one line
+    #[cfg(unix)]
+    fn status(&self) -> String {
+        let active = self.tasks.iter().find(|t| t.is_active);
+        let selected = self.tasks.iter().find(|t| t.is_selected);
+
+        serde_json::json!({
+            "active": active.map(|t| &t.title),
+            "active_secs": active.map(|t| t.elapsed_time.as_secs()),
+            "selected": selected.map(|t| &t.title),
+            "today_secs": self.tracked_today().as_secs(),
+            "open_tasks": self.tasks.iter().filter(|t| !t.is_done).count(),
+            "unsaved": self.is_dirty(),
+        })
+        .to_string()
+    }
+
+    // Time tracked today over all tasks, archived ones included
+    fn tracked_today(&self) -> Duration {
+        let today = today();
+        self.tasks
+            .iter()
+            .chain(self.archive.iter().flat_map(|a| a.tasks.iter()))
+            .map(|t| t.time_on(today))
+            .sum()
+    }
+
     fn activate_task(&mut self) {
         for task in &mut self.tasks {
             // For the current active task do the ellapsed time and reset it
-            if task.is_active {
-                task.toggle_active();
-            } else if task.is_selected && !task.is_done {
+            if task.is_active || (task.is_selected && !task.is_done) {
                 task.toggle_active();
             }
         }
@@ -800,10 +1652,8 @@ impl App {
     }
 
     fn inc_arch_item(&mut self) {
-        if self.archive.len() > 0 {
-            if self.curr_archive < self.archive.len() - 1 {
-                self.curr_archive += 1;
-            }
+        if !self.archive.is_empty() && self.curr_archive < self.archive.len() - 1 {
+            self.curr_archive += 1;
         }
     }
 
@@ -836,11 +1686,14 @@ impl App {
             }
         }
 
-        if reset_selection && self.tasks.len() > 0 {
+        if reset_selection && !self.tasks.is_empty() {
             self.tasks[0].is_selected = true;
         }
 
-        if new_arch_item.tasks.len() > 0 {
+        let count = new_arch_item.tasks.len();
+        self.show_message(format!("Archived {} task{}", count, if count == 1 { "" } else { "s" }));
+
+        if !new_arch_item.tasks.is_empty() {
             new_arch_item.tasks[0].is_selected = true;
             self.archive.push(new_arch_item.clone());
             self.curr_archive = self.archive.len() - 1;
@@ -848,7 +1701,7 @@ impl App {
     }
 
     fn dearchive_task(&mut self) {
-        if self.archive.len() > 0 {
+        if !self.archive.is_empty() {
             let mut index = 0;
 
             while index < self.archive[self.curr_archive].tasks.len() {
@@ -859,7 +1712,7 @@ impl App {
 
                     self.archive[self.curr_archive].tasks.remove(index);
 
-                    if self.archive[self.curr_archive].tasks.len() > 0 {
+                    if !self.archive[self.curr_archive].tasks.is_empty() {
                         if index < self.archive[self.curr_archive].tasks.len() {
                             self.archive[self.curr_archive].tasks[index].is_selected = true;
                         } else {
@@ -871,10 +1724,10 @@ impl App {
                 index += 1;
             }
 
-            if self.archive[self.curr_archive].tasks.len() == 0 {
+            if self.archive[self.curr_archive].tasks.is_empty() {
                 self.archive.remove(self.curr_archive);
 
-                if self.archive.len() == 0 {
+                if self.archive.is_empty() {
                     self.curr_archive = 0;
                 } else if self.curr_archive >= self.archive.len() {
                     self.curr_archive = self.archive.len() - 1;
@@ -884,14 +1737,14 @@ impl App {
     }
 
     fn get_curr_archive_item(&self) -> Option<ArchiveItem> {
-        if self.archive.len() > 0 {
+        if !self.archive.is_empty() {
             let active_archive = self.archive[self.curr_archive].clone();
             return Some(active_archive);
         }
         None
     }
 
-    fn get_sel_task_info(&mut self) -> Option<Vec<Spans>> {
+    fn get_sel_task_info(&mut self) -> Option<Vec<Spans<'_>>> {
         match self.state {
             AppState::Display => {
                 for task in &self.tasks {
@@ -927,22 +1780,20 @@ impl App {
                     }
                 }
             },
-            AppState::Archived => {
-                if self.archive.len() > 0 {
-                    for task in &self.archive[self.curr_archive].tasks {
-                        if task.is_selected {
-                            let mut spans: Vec<Spans> = vec![];
-
-                            self.disp_string = String::from("\n");
-                            self.disp_string.push_str(&task.description);
-                            let lines: Vec<&str> = self.disp_string.split("\n").collect();
-
-                            for line in lines {
-                                spans.push(Spans::from(vec![Span::styled(line, self.settings.default)]));
-                            }
+            AppState::Archived if !self.archive.is_empty() => {
+                for task in &self.archive[self.curr_archive].tasks {
+                    if task.is_selected {
+                        let mut spans: Vec<Spans> = vec![];
+
+                        self.disp_string = String::from("\n");
+                        self.disp_string.push_str(&task.description);
+                        let lines: Vec<&str> = self.disp_string.split("\n").collect();
 
-                            return Some(spans);
+                        for line in lines {
+                            spans.push(Spans::from(vec![Span::styled(line, self.settings.default)]));
                         }
+
+                        return Some(spans);
                     }
                 }
             },
@@ -952,54 +1803,51 @@ impl App {
         None
     }
 
-    fn get_sel_task_info_editable(&mut self) -> Option<Vec<Spans>> {
-        match self.state {
-            AppState::EditTask => {
-                for task in &self.tasks {
-                    if task.is_selected {
-                        let mut spans: Vec<Spans> = vec![];
-                        if self.edit_field == EditField::Description {
-                            if self.last_blink.elapsed() > BLINK_TIME {
-                                self.cursor_shown = !self.cursor_shown;
-                                self.last_blink = Instant::now();
-                            }
+    fn get_sel_task_info_editable(&mut self) -> Option<Vec<Spans<'_>>> {
+        if self.state == AppState::EditTask {
+            for task in &self.tasks {
+                if task.is_selected {
+                    let mut spans: Vec<Spans> = vec![];
+                    if self.edit_field == EditField::Description {
+                        if self.last_blink.elapsed() > BLINK_TIME {
+                            self.cursor_shown = !self.cursor_shown;
+                            self.last_blink = Instant::now();
+                        }
 
-                            let blink_char = if self.cursor_shown {
-                                '_'
-                            } else if self.blink_char == '\n' {
-                                ' '
-                            } else {
-                                self.blink_char
-                            };
-
-                            self.disp_string = String::from("\n");
-                            self.disp_string.push_str(&self.first_string);
-                            self.disp_string.push(blink_char);
-                            if self.blink_char == '\n' {
-                                self.disp_string.push('\n');
-                            }
-                            self.disp_string.push_str(&self.second_string);
+                        let blink_char = if self.cursor_shown {
+                            '_'
+                        } else if self.blink_char == '\n' {
+                            ' '
+                        } else {
+                            self.blink_char
+                        };
 
-                            let lines: Vec<&str> = self.disp_string.split("\n").collect();
+                        self.disp_string = String::from("\n");
+                        self.disp_string.push_str(&self.first_string);
+                        self.disp_string.push(blink_char);
+                        if self.blink_char == '\n' {
+                            self.disp_string.push('\n');
+                        }
+                        self.disp_string.push_str(&self.second_string);
 
-                            for line in lines {
-                                spans.push(Spans::from(vec![Span::styled(line, self.settings.default)]));
-                            }
-                        } else {
-                            self.disp_string = String::from("\n");
-                            self.disp_string.push_str(&task.description);
-                            let lines: Vec<&str> = self.disp_string.split("\n").collect();
+                        let lines: Vec<&str> = self.disp_string.split("\n").collect();
 
-                            for line in lines {
-                                spans.push(Spans::from(vec![Span::styled(line, self.settings.default)]));
-                            }
+                        for line in lines {
+                            spans.push(Spans::from(vec![Span::styled(line, self.settings.default)]));
                         }
+                    } else {
+                        self.disp_string = String::from("\n");
+                        self.disp_string.push_str(&task.description);
+                        let lines: Vec<&str> = self.disp_string.split("\n").collect();
 
-                        return Some(spans);
+                        for line in lines {
+                            spans.push(Spans::from(vec![Span::styled(line, self.settings.default)]));
+                        }
                     }
+
+                    return Some(spans);
                 }
-            },
-            _ => {}
+            }
         }
 
         None
@@ -1021,12 +1869,10 @@ impl App {
                     }
                 }
             },
-            AppState::Archived => {
-                if self.archive.len() > 0 {
-                    for task in &self.archive[self.curr_archive].tasks {
-                        if task.is_selected {
-                            return Some(task.title.clone());
-                        }
+            AppState::Archived if !self.archive.is_empty() => {
+                for task in &self.archive[self.curr_archive].tasks {
+                    if task.is_selected {
+                        return Some(task.title.clone());
                     }
                 }
             },
@@ -1037,36 +1883,33 @@ impl App {
     }
 
     fn get_sel_task_title_editable(&mut self) -> Option<String> {
-        match self.state {
-            AppState::EditTask => {
-                for task in &self.tasks {
-                    if task.is_selected {
-                        if self.edit_field == EditField::Title {
-                            if self.last_blink.elapsed() > BLINK_TIME {
-                                self.cursor_shown = !self.cursor_shown;
-                                self.last_blink = Instant::now();
-                            }
+        if self.state == AppState::EditTask {
+            for task in &self.tasks {
+                if task.is_selected {
+                    if self.edit_field == EditField::Title {
+                        if self.last_blink.elapsed() > BLINK_TIME {
+                            self.cursor_shown = !self.cursor_shown;
+                            self.last_blink = Instant::now();
+                        }
 
-                            let blink_char = if self.cursor_shown {
-                                '_'
-                            } else if self.blink_char == '\n' {
-                                ' '
-                            } else {
-                                self.blink_char
-                            };
+                        let blink_char = if self.cursor_shown {
+                            '_'
+                        } else if self.blink_char == '\n' {
+                            ' '
+                        } else {
+                            self.blink_char
+                        };
 
-                            self.disp_string = self.first_string.clone();
-                            self.disp_string.push(blink_char);
-                            self.disp_string.push_str(&self.second_string);
+                        self.disp_string = self.first_string.clone();
+                        self.disp_string.push(blink_char);
+                        self.disp_string.push_str(&self.second_string);
 
-                            return Some(self.disp_string.clone());
-                        } else {
-                            return Some(task.title.clone());
-                        }
+                        return Some(self.disp_string.clone());
+                    } else {
+                        return Some(task.title.clone());
                     }
                 }
-            },
-            _ => {},
+            }
         }
 
         None
@@ -1089,6 +1932,7 @@ impl App {
             task.is_selected = false;
         }
         let task = Task {
+            id: Uuid::new_v4(),
             title: String::from(""),
             description: String::from(""),
             is_done: false,
@@ -1096,6 +1940,10 @@ impl App {
             is_selected: true,
             elapsed_time: Duration::new(0, 0),
             created_on: Utc::now(),
+            priority: None,
+            due: None,
+            daily_time: BTreeMap::new(),
+            sessions: vec![],
         };
         self.tasks.push(task.clone());
 
@@ -1111,7 +1959,7 @@ impl App {
             if self.tasks[index].is_selected {
                 self.tasks.remove(index);
 
-                if self.tasks.len() > 0 {
+                if !self.tasks.is_empty() {
                     if index < self.tasks.len() {
                         self.tasks[index].is_selected = true;
                     } else {
@@ -1126,33 +1974,67 @@ impl App {
 
     fn inc_setting_selection(&mut self) {
         match self.edit_setting {
-            EditSettingField::Split => self.edit_setting = EditSettingField::NormalFg,
+            EditSettingField::Split => self.edit_setting = EditSettingField::AutosaveDelay,
+            EditSettingField::AutosaveDelay => self.edit_setting = EditSettingField::Checkpoint,
+            EditSettingField::Checkpoint => self.edit_setting = EditSettingField::CalendarFile,
+            EditSettingField::CalendarFile => self.edit_setting = EditSettingField::Theme,
+            EditSettingField::Theme => self.edit_setting = EditSettingField::NormalFg,
             EditSettingField::NormalFg => self.edit_setting = EditSettingField::NormalBg,
             EditSettingField::NormalBg => self.edit_setting = EditSettingField::SelectionFg,
             EditSettingField::SelectionFg => self.edit_setting = EditSettingField::SelectionBg,
             EditSettingField::SelectionBg => self.edit_setting = EditSettingField::Active,
             EditSettingField::Active => self.edit_setting = EditSettingField::Title,
             EditSettingField::Title => self.edit_setting = EditSettingField::Border,
+            EditSettingField::Border => self.edit_setting = EditSettingField::NormalMod,
+            EditSettingField::NormalMod => self.edit_setting = EditSettingField::SelectionMod,
+            EditSettingField::SelectionMod => self.edit_setting = EditSettingField::ActiveMod,
+            EditSettingField::ActiveMod => self.edit_setting = EditSettingField::TitleMod,
+            EditSettingField::TitleMod => self.edit_setting = EditSettingField::BorderMod,
             _ => {},
         }
     }
 
     fn dec_setting_selection(&mut self) {
         match self.edit_setting {
-            EditSettingField::NormalFg => self.edit_setting = EditSettingField::Split,
+            EditSettingField::AutosaveDelay => self.edit_setting = EditSettingField::Split,
+            EditSettingField::Checkpoint => self.edit_setting = EditSettingField::AutosaveDelay,
+            EditSettingField::CalendarFile => self.edit_setting = EditSettingField::Checkpoint,
+            EditSettingField::Theme => self.edit_setting = EditSettingField::CalendarFile,
+            EditSettingField::NormalFg => self.edit_setting = EditSettingField::Theme,
             EditSettingField::NormalBg => self.edit_setting = EditSettingField::NormalFg,
             EditSettingField::SelectionFg => self.edit_setting = EditSettingField::NormalBg,
             EditSettingField::SelectionBg => self.edit_setting = EditSettingField::SelectionFg,
             EditSettingField::Active => self.edit_setting = EditSettingField::SelectionBg,
             EditSettingField::Title => self.edit_setting = EditSettingField::Active,
             EditSettingField::Border => self.edit_setting = EditSettingField::Title,
+            EditSettingField::NormalMod => self.edit_setting = EditSettingField::Border,
+            EditSettingField::SelectionMod => self.edit_setting = EditSettingField::NormalMod,
+            EditSettingField::ActiveMod => self.edit_setting = EditSettingField::SelectionMod,
+            EditSettingField::TitleMod => self.edit_setting = EditSettingField::ActiveMod,
+            EditSettingField::BorderMod => self.edit_setting = EditSettingField::TitleMod,
             _ => {},
         }
     }
 
+    // The calendar is written as soon as it is turned on, not on the next save
+    fn toggle_calendar_file(&mut self) {
+        self.settings.calendar_file = !self.settings.calendar_file;
+
+        if self.settings.calendar_file && !self.read_only {
+            match formats::save_calendar(&self.data_dir, &self.tasks, &self.archive) {
+                Ok(()) => self.show_message(format!("Writing the calendar to {}", formats::CALENDAR_FILE)),
+                Err(err) => self.show_error(format!("Could not write the calendar: {}", err)),
+            }
+        }
+    }
+
     fn inc_setting(&mut self) {
         match self.edit_setting {
             EditSettingField::Split => self.settings.is_horizontal = !self.settings.is_horizontal,
+            EditSettingField::AutosaveDelay => self.settings.autosave_secs = next_step(&AUTOSAVE_STEPS, self.settings.autosave_secs),
+            EditSettingField::Checkpoint => self.settings.checkpoint_secs = next_step(&CHECKPOINT_STEPS, self.settings.checkpoint_secs),
+            EditSettingField::CalendarFile => self.toggle_calendar_file(),
+            EditSettingField::Theme => self.cycle_theme(true),
             EditSettingField::NormalFg => {self.settings.normal_fg_colour = next_colour(self.settings.normal_fg_colour); self.settings.set_colours()},
             EditSettingField::NormalBg => {self.settings.normal_bg_colour = next_colour(self.settings.normal_bg_colour); self.settings.set_colours()},
             EditSettingField::SelectionFg => {self.settings.select_fg_colour = next_colour(self.settings.select_fg_colour); self.settings.set_colours()},
@@ -1160,12 +2042,25 @@ impl App {
             EditSettingField::Active => {self.settings.active_fg_colour = next_colour(self.settings.active_fg_colour); self.settings.set_colours()},
             EditSettingField::Title => {self.settings.title_fg_colour = next_colour(self.settings.title_fg_colour); self.settings.set_colours()},
             EditSettingField::Border => {self.settings.border_colour = next_colour(self.settings.border_colour); self.settings.set_colours()},
+            EditSettingField::NormalMod => {self.settings.normal_modifiers = next_modifier(self.settings.normal_modifiers); self.settings.set_colours()},
+            EditSettingField::SelectionMod => {self.settings.select_modifiers = next_modifier(self.settings.select_modifiers); self.settings.set_colours()},
+            EditSettingField::ActiveMod => {self.settings.active_modifiers = next_modifier(self.settings.active_modifiers); self.settings.set_colours()},
+            EditSettingField::TitleMod => {self.settings.title_modifiers = next_modifier(self.settings.title_modifiers); self.settings.set_colours()},
+            EditSettingField::BorderMod => {self.settings.border_modifiers = next_modifier(self.settings.border_modifiers); self.settings.set_colours()},
+        }
+
+        if self.edit_setting.is_style() {
+            self.settings.theme.clear();
         }
     }
 
     fn dec_setting(&mut self) {
         match self.edit_setting {
             EditSettingField::Split => self.settings.is_horizontal = !self.settings.is_horizontal,
+            EditSettingField::AutosaveDelay => self.settings.autosave_secs = prev_step(&AUTOSAVE_STEPS, self.settings.autosave_secs),
+            EditSettingField::Checkpoint => self.settings.checkpoint_secs = prev_step(&CHECKPOINT_STEPS, self.settings.checkpoint_secs),
+            EditSettingField::CalendarFile => self.toggle_calendar_file(),
+            EditSettingField::Theme => self.cycle_theme(false),
             EditSettingField::NormalFg => {self.settings.normal_fg_colour = prev_colour(self.settings.normal_fg_colour); self.settings.set_colours()},
             EditSettingField::NormalBg => {self.settings.normal_bg_colour = prev_colour(self.settings.normal_bg_colour); self.settings.set_colours()},
             EditSettingField::SelectionFg => {self.settings.select_fg_colour = prev_colour(self.settings.select_fg_colour); self.settings.set_colours()},
@@ -1173,6 +2068,98 @@ impl App {
             EditSettingField::Active => {self.settings.active_fg_colour = prev_colour(self.settings.active_fg_colour); self.settings.set_colours()},
             EditSettingField::Title => {self.settings.title_fg_colour = prev_colour(self.settings.title_fg_colour); self.settings.set_colours()},
             EditSettingField::Border => {self.settings.border_colour = prev_colour(self.settings.border_colour); self.settings.set_colours()},
+            EditSettingField::NormalMod => {self.settings.normal_modifiers = prev_modifier(self.settings.normal_modifiers); self.settings.set_colours()},
+            EditSettingField::SelectionMod => {self.settings.select_modifiers = prev_modifier(self.settings.select_modifiers); self.settings.set_colours()},
+            EditSettingField::ActiveMod => {self.settings.active_modifiers = prev_modifier(self.settings.active_modifiers); self.settings.set_colours()},
+            EditSettingField::TitleMod => {self.settings.title_modifiers = prev_modifier(self.settings.title_modifiers); self.settings.set_colours()},
+            EditSettingField::BorderMod => {self.settings.border_modifiers = prev_modifier(self.settings.border_modifiers); self.settings.set_colours()},
         }
+
+        if self.edit_setting.is_style() {
+            self.settings.theme.clear();
+        }
+    }
+
+    fn themes_dir(&self) -> PathBuf {
+        self.config_dir.join("themes")
     }
-}
\ No newline at end of file
+
+    // Switches to a theme chosen by name, e.g. on the command line
+    pub fn use_theme(&mut self, name: &str) -> Result<()> {
+        match find_theme(&self.themes_dir(), name) {
+            Some(theme) => {
+                self.settings.apply_theme(&theme);
+                Ok(())
+            },
+            None => {
+                let names: Vec<String> = available_themes(&self.themes_dir()).into_iter().map(|t| t.name).collect();
+                Err(Error::Usage(format!("no theme called '{}', choose from: {}", name, names.join(", "))))
+            },
+        }
+    }
+
+    fn cycle_theme(&mut self, forward: bool) {
+        let themes = available_themes(&self.themes_dir());
+        let next = match themes.iter().position(|t| t.name == self.settings.theme) {
+            Some(index) if forward => (index + 1) % themes.len(),
+            Some(index) => (index + themes.len() - 1) % themes.len(),
+            None => 0,
+        };
+
+        self.settings.apply_theme(&themes[next]);
+    }
+
+    // Returns the colour edited by the selected settings field, if it is a colour
+    fn selected_colour(&mut self) -> Option<&mut Color> {
+        match self.edit_setting {
+            EditSettingField::NormalFg => Some(&mut self.settings.normal_fg_colour),
+            EditSettingField::NormalBg => Some(&mut self.settings.normal_bg_colour),
+            EditSettingField::SelectionFg => Some(&mut self.settings.select_fg_colour),
+            EditSettingField::SelectionBg => Some(&mut self.settings.select_bg_colour),
+            EditSettingField::Active => Some(&mut self.settings.active_fg_colour),
+            EditSettingField::Title => Some(&mut self.settings.title_fg_colour),
+            EditSettingField::Border => Some(&mut self.settings.border_colour),
+            _ => None,
+        }
+    }
+
+    // Sets the selected colour or saves the theme from the typed input, invalid
+    // input is kept for correcting
+    fn apply_setting_input(&mut self) {
+        let input = match &self.setting_input {
+            Some(input) => input.trim().to_string(),
+            None => return,
+        };
+
+        if self.edit_setting == EditSettingField::Theme {
+            if is_valid_theme_name(&input) {
+                let theme = self.settings.to_theme(&input);
+                match save_theme(&self.themes_dir(), &theme) {
+                    Ok(()) => {
+                        self.show_message(format!("Saved theme '{}'", input));
+                        self.settings.theme = input;
+                        self.setting_input = None;
+                    },
+                    Err(err) => self.show_error(format!("Could not save theme: {}", err)),
+                }
+            }
+            return;
+        }
+
+        let parsed = parse_colour(&input);
+
+        if let Some(colour) = parsed {
+            if let Some(selected) = self.selected_colour() {
+                *selected = colour;
+            }
+            self.settings.set_colours();
+            self.settings.theme.clear();
+            self.setting_input = None;
+        }
+    }
+}
+
+// Tracked time is kept by local date
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
@@ -0,0 +1,137 @@
+// ----------------------------------------------------------------------------
+// CONTROL SUB-MODULE
+// This submodule lets other programs control a running app through a Unix
+// socket in the data folder, e.g. to start the timer from a hotkey. Every line
+// sent is a command, and the app answers each one with a line:
+//   toggle-active    start or stop the timer of the selected task
+//   add <title>      add a task
+//   done-selected    mark the selected task as done
+//   status           the running task and the time tracked today, as JSON
+// Commands go through the same channel as the keys, so they are handled and
+// drawn at once, and the answer is "ok", "error: ..." or the status.
+// ----------------------------------------------------------------------------
+
+use super::Event;
+
+use crate::error::{Error, Result};
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crossterm::event::KeyEvent;
+
+// ---- CONSTANTS ----
+pub const SOCKET_FILE: &str = "todo_rust.sock";
+
+// How long a command waits for the app, it may be busy saving
+const REPLY_TIME: Duration = Duration::from_secs(5);
+
+pub enum Command {
+    ToggleActive,
+    Add(String),
+    DoneSelected,
+    Status,
+}
+
+impl Command {
+    fn parse(line: &str) -> std::result::Result<Command, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        match (name, rest.trim()) {
+            ("toggle-active", "") => Ok(Command::ToggleActive),
+            ("done-selected", "") => Ok(Command::DoneSelected),
+            ("status", "") => Ok(Command::Status),
+            ("add", "") => Err(String::from("add needs a title")),
+            ("add", title) => Ok(Command::Add(title.to_string())),
+            _ => Err(format!("unknown command '{}', expected toggle-active, add <title>, done-selected or status", line)),
+        }
+    }
+}
+
+// A command and where to send the answer
+pub struct Request {
+    pub command: Command,
+    pub reply: Sender<String>,
+}
+
+// Removes the socket when the app stops listening
+pub struct ControlSocket {
+    path: PathBuf,
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Listens on the socket of the data folder, sending the commands to the app.
+// Only the instance holding the lock listens, so a socket left by one that
+// crashed can be replaced.
+pub(super) fn listen(data_dir: &Path, events: Sender<Event<KeyEvent>>) -> Result<ControlSocket> {
+    let path = data_dir.join(SOCKET_FILE);
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let events = events.clone();
+            thread::spawn(move || serve_client(stream, events));
+        }
+    });
+
+    Ok(ControlSocket { path })
+}
+
+fn serve_client(stream: UnixStream, events: Sender<Event<KeyEvent>>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let answer = match Command::parse(&line) {
+            Ok(command) => {
+                let (reply, answer) = mpsc::channel();
+                if events.send(Event::Remote(Request { command, reply })).is_err() {
+                    // The app has quit
+                    return;
+                }
+                answer.recv_timeout(REPLY_TIME).unwrap_or_else(|_| String::from("error: the app didn't answer"))
+            },
+            Err(message) => format!("error: {}", message),
+        };
+
+        if writeln!(writer, "{}", answer).is_err() {
+            return;
+        }
+    }
+}
+
+// Sends a command to the app running on the data folder and returns its answer
+pub fn send(data_dir: &Path, command: &str) -> Result<String> {
+    let path = data_dir.join(SOCKET_FILE);
+    let mut stream = UnixStream::connect(&path)
+        .map_err(|err| Error::Storage(format!("no todo_rust is running on {} ({})", data_dir.display(), err)))?;
+
+    writeln!(stream, "{}", command.trim())?;
+
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+
+    Ok(answer.trim_end().to_string())
+}
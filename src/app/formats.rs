@@ -0,0 +1,293 @@
+// ----------------------------------------------------------------------------
+// FORMATS SUB-MODULE
+// This submodule moves tasks between the data folder and the files of other
+// tools. Exports read the tasks and the archive as they are saved and imports
+// add to them, so they run on the files and not on a running app.
+// ----------------------------------------------------------------------------
+
+mod todotxt;
+mod taskwarrior;
+mod markdown;
+mod ical;
+mod csv_file;
+mod org;
+
+use super::ArchiveItem;
+use super::task::Task;
+use super::schema::{self, DataFile};
+
+use crate::error::{Error, Result};
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+
+use clap::ValueEnum;
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+pub const CALENDAR_FILE: &str = "tasks.ics";
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Format {
+    /// todo.txt, one task per line
+    Todotxt,
+    /// Taskwarrior JSON, as read by task import
+    Taskwarrior,
+    /// Markdown task lists, - [ ] title
+    Markdown,
+    /// iCalendar, open tasks and tracked time (export only)
+    Ical,
+    /// CSV, one row per task
+    Csv,
+    /// Org-mode headings with TODO and DONE
+    Org,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum TimeFormat {
+    /// Hours with decimals, 1.50
+    Decimal,
+    /// Hours, minutes and seconds, 1:30:00
+    Clock,
+}
+
+// What to export and how, the delimiter and time format are for CSV only
+pub struct ExportOptions {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub delimiter: u8,
+    pub time_format: TimeFormat,
+}
+
+// The tasks and the archive of a data folder, or of an imported file
+#[derive(Default)]
+struct Data {
+    tasks: Vec<Task>,
+    archive: Vec<ArchiveItem>,
+}
+
+impl Data {
+    fn load(data_dir: &Path) -> Result<Data> {
+        let tasks_path = data_dir.join("tasks.json");
+        let archive_path = data_dir.join("archive.json");
+
+        Ok(Data {
+            tasks: if tasks_path.exists() { schema::load(&tasks_path, DataFile::Tasks)? } else { vec![] },
+            archive: if archive_path.exists() { schema::load(&archive_path, DataFile::Archive)? } else { vec![] },
+        })
+    }
+
+    fn save(&self, data_dir: &Path) -> Result<()> {
+        schema::save(&data_dir.join("tasks.json"), &self.tasks, DataFile::Tasks)?;
+        schema::save(&data_dir.join("archive.json"), &self.archive, DataFile::Archive)?;
+
+        Ok(())
+    }
+
+    // Adds the tasks of another file, archive batches of the same moment are
+    // joined and the rest kept in date order. Tasks that are already here are
+    // skipped, so a file can be imported again. Returns how many were added.
+    fn append(&mut self, other: Data) -> usize {
+        let mut known: HashSet<Uuid> = self.tasks.iter().map(|t| t.id).collect();
+        known.extend(self.archive.iter().flat_map(|a| a.tasks.iter().map(|t| t.id)));
+
+        let mut count = 0;
+
+        for mut task in other.tasks {
+            if known.insert(task.id) {
+                task.is_selected = false;
+                task.is_active = false;
+                self.tasks.push(task);
+                count += 1;
+            }
+        }
+
+        for item in other.archive {
+            let mut tasks: Vec<Task> = item.tasks.into_iter().filter(|t| known.insert(t.id)).collect();
+            if tasks.is_empty() {
+                continue;
+            }
+
+            count += tasks.len();
+            for task in &mut tasks {
+                task.is_selected = false;
+                task.is_active = false;
+            }
+
+            match self.archive.iter_mut().find(|a| a.date == item.date) {
+                Some(existing) => existing.tasks.append(&mut tasks),
+                None => {
+                    let index = self.archive.iter().position(|a| a.date > item.date).unwrap_or(self.archive.len());
+                    self.archive.insert(index, ArchiveItem { date: item.date, tasks });
+                },
+            }
+        }
+
+        count
+    }
+
+    // Keeps the archive batches from the days in the range, and the tasks of
+    // the list created in it
+    fn within(mut self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Data {
+        let in_range = |date: DateTime<Utc>| {
+            let day = local_date(date);
+            from.is_none_or(|from| day >= from) && to.is_none_or(|to| day <= to)
+        };
+
+        self.tasks.retain(|t| in_range(t.created_on));
+        self.archive.retain(|a| in_range(a.date));
+
+        self
+    }
+}
+
+// Writes the tasks and the archive to the file, or to stdout without one
+pub fn export(data_dir: &Path, format: Format, output: Option<&Path>, options: &ExportOptions) -> Result<()> {
+    let data = Data::load(data_dir)?.within(options.from, options.to);
+
+    let text = match format {
+        Format::Todotxt => todotxt::write(&data),
+        Format::Taskwarrior => taskwarrior::write(&data)?,
+        Format::Markdown => markdown::write(&data),
+        Format::Ical => ical::write(&data),
+        Format::Csv => csv_file::write(&data, options.delimiter, options.time_format)?,
+        Format::Org => org::write(&data),
+    };
+
+    match output {
+        Some(path) => fs::write(path, text)?,
+        None => io::stdout().write_all(text.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+// Adds the tasks of the file, or of stdin without one, returns how many there were
+pub fn import(data_dir: &Path, format: Format, input: Option<&Path>) -> Result<usize> {
+    let read: fn(&str) -> Result<Data> = match format {
+        Format::Todotxt => todotxt::read,
+        Format::Taskwarrior => taskwarrior::read,
+        Format::Markdown => markdown::read,
+        Format::Csv => csv_file::read,
+        Format::Org => org::read,
+        Format::Ical => return Err(Error::Usage(String::from("iCalendar files can only be exported"))),
+    };
+
+    let text = match input {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        },
+    };
+    let imported = read(&text)?;
+
+    let mut data = Data::load(data_dir)?;
+    let count = data.append(imported);
+    data.save(data_dir)?;
+
+    Ok(count)
+}
+
+// Keeps the calendar file in the data folder up to date, for calendar apps to follow
+pub(super) fn save_calendar(data_dir: &Path, tasks: &[Task], archive: &[ArchiveItem]) -> Result<()> {
+    schema::write_file(&data_dir.join(CALENDAR_FILE), ical::calendar(tasks, archive).as_bytes())
+}
+
+// ---- DATES ----
+// Files of other tools have dates without times, they are taken in local time
+fn local_date(date: DateTime<Utc>) -> NaiveDate {
+    date.with_timezone(&Local).date_naive()
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local.from_local_datetime(&midnight).earliest().map(|d| d.with_timezone(&Utc)).unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+// Whole seconds, with the fraction only when there is one
+fn secs_to_string(time: Duration) -> String {
+    if time.subsec_nanos() == 0 {
+        time.as_secs().to_string()
+    } else {
+        format!("{}.{:09}", time.as_secs(), time.subsec_nanos()).trim_end_matches('0').to_string()
+    }
+}
+
+fn parse_secs(text: &str) -> Option<Duration> {
+    let (secs, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if fraction.len() > 9 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let nanos = if fraction.is_empty() { 0 } else { format!("{:0<9}", fraction).parse().ok()? };
+    Some(Duration::new(secs.parse().ok()?, nanos))
+}
+
+// Tracked time per day, as day=seconds separated by commas
+fn days_to_string(days: &BTreeMap<NaiveDate, Duration>) -> String {
+    let days: Vec<String> = days
+        .iter()
+        .map(|(day, time)| format!("{}={}", day.format("%Y-%m-%d"), secs_to_string(*time)))
+        .collect();
+
+    days.join(",")
+}
+
+fn parse_days(text: &str) -> Option<BTreeMap<NaiveDate, Duration>> {
+    text
+        .split(',')
+        .map(|day| {
+            let (date, secs) = day.split_once('=')?;
+            Some((NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?, parse_secs(secs)?))
+        })
+        .collect()
+}
+
+// Tags are written in titles as +tag, like todo.txt projects. Returns the
+// title without them and the tags.
+fn split_tags(title: &str) -> (String, Vec<String>) {
+    let mut words = vec![];
+    let mut tags = vec![];
+
+    for word in title.split(' ') {
+        match word.strip_prefix('+') {
+            Some(tag) if !tag.is_empty() => tags.push(tag.to_string()),
+            _ => words.push(word),
+        }
+    }
+
+    (words.join(" ").trim().to_string(), tags)
+}
+
+fn join_tags(title: &str, tags: &[String]) -> String {
+    let mut title = title.to_string();
+    for tag in tags {
+        title.push_str(" +");
+        title.push_str(tag);
+    }
+
+    title
+}
+
+// Groups tasks by the moment they were archived, in date order
+fn archive_from(tasks: Vec<(DateTime<Utc>, Task)>) -> Vec<ArchiveItem> {
+    let mut archive: Vec<ArchiveItem> = vec![];
+
+    for (date, task) in tasks {
+        match archive.iter_mut().find(|a| a.date == date) {
+            Some(item) => item.tasks.push(task),
+            None => archive.push(ArchiveItem { date, tasks: vec![task] }),
+        }
+    }
+
+    archive.sort_by_key(|a| a.date);
+    archive
+}
@@ -0,0 +1,180 @@
+// ----------------------------------------------------------------------------
+// CSV SUB-MODULE
+// This submodule writes the tasks and the archive as CSV for spreadsheets, one
+// row per task, and reads them back by the names of the columns so files that
+// were reordered or trimmed in a spreadsheet can still be imported. Only the
+// title column is needed, a task with an archive date goes to the archive.
+// ----------------------------------------------------------------------------
+
+use super::{archive_from, start_of_day, Data, TimeFormat};
+use super::super::task::Task;
+
+use crate::error::{Error, Result};
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+const COLUMNS: &[&str] = &[
+    "archived_on", "title", "description", "done", "created_on", "due", "priority",
+    "elapsed_seconds", "elapsed_hours", "id",
+];
+
+// Delimiters looked for in the header of imported files
+const DELIMITERS: &[u8] = b",;\t|";
+
+pub fn write(data: &Data, delimiter: u8, time_format: TimeFormat) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(vec![]);
+    writer.write_record(COLUMNS).map_err(csv_error)?;
+
+    let rows = data.tasks
+        .iter()
+        .map(|task| (None, task))
+        .chain(data.archive.iter().flat_map(|a| a.tasks.iter().map(move |task| (Some(a.date), task))));
+
+    for (archived_on, task) in rows {
+        writer.write_record([
+            archived_on.map(local_time).unwrap_or_default(),
+            task.title.clone(),
+            task.description.clone(),
+            task.is_done.to_string(),
+            local_time(task.created_on),
+            task.due.map(|d| d.format(DATE_FORMAT).to_string()).unwrap_or_default(),
+            task.priority.map(String::from).unwrap_or_default(),
+            task.elapsed_time.as_secs().to_string(),
+            hours_to_string(task.elapsed_time, time_format),
+            task.id.to_string(),
+        ]).map_err(csv_error)?;
+    }
+
+    let bytes = writer.into_inner().map_err(|err| Error::Storage(err.to_string()))?;
+    String::from_utf8(bytes).map_err(|err| Error::Storage(err.to_string()))
+}
+
+pub fn read(text: &str) -> Result<Data> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(find_delimiter(text))
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let columns: HashMap<String, usize> = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_lowercase(), index))
+        .collect();
+
+    if !columns.contains_key("title") {
+        return Err(Error::Storage(String::from("the CSV file has no title column")));
+    }
+
+    let mut data = Data::default();
+    let mut archived = vec![];
+
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let field = |name: &str| columns.get(name).and_then(|i| record.get(*i)).map(str::trim).filter(|f| !f.is_empty());
+
+        let elapsed_secs = field("elapsed_seconds")
+            .and_then(|s| s.parse::<f64>().ok())
+            .or_else(|| field("elapsed_hours").and_then(parse_hours).map(|h| h * 3600.0));
+
+        // Negative and huge times can't be tracked, the row is reported
+        let elapsed_time = match elapsed_secs {
+            Some(secs) => Duration::try_from_secs_f64(secs).map_err(|_| {
+                let line = record.position().map_or(0, |p| p.line());
+                Error::Storage(format!("CSV line {}: {} seconds is not a valid elapsed time", line, secs))
+            })?,
+            None => Duration::default(),
+        };
+
+        let task = Task {
+            id: field("id").and_then(|id| Uuid::parse_str(id).ok()).unwrap_or_else(Uuid::new_v4),
+            title: field("title").unwrap_or("").to_string(),
+            description: columns.get("description").and_then(|i| record.get(*i)).unwrap_or("").to_string(),
+            is_done: field("done").is_some_and(parse_bool),
+            elapsed_time,
+            created_on: field("created_on").and_then(parse_time).unwrap_or_else(Utc::now),
+            priority: field("priority").and_then(|p| p.chars().next()).filter(char::is_ascii_uppercase),
+            due: field("due").and_then(|d| NaiveDate::parse_from_str(d, DATE_FORMAT).ok()),
+            ..Task::default()
+        };
+
+        match field("archived_on").and_then(parse_time) {
+            Some(date) => archived.push((date, Task { is_done: true, ..task })),
+            None => data.tasks.push(task),
+        }
+    }
+
+    data.archive = archive_from(archived);
+
+    Ok(data)
+}
+
+fn csv_error(err: csv::Error) -> Error {
+    Error::Storage(format!("CSV: {}", err))
+}
+
+fn local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format(DATE_TIME_FORMAT).to_string()
+}
+
+fn hours_to_string(time: Duration, time_format: TimeFormat) -> String {
+    match time_format {
+        TimeFormat::Decimal => format!("{:.2}", time.as_secs_f64() / 3600.0),
+        TimeFormat::Clock => {
+            let secs = time.as_secs();
+            format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+        },
+    }
+}
+
+// Decimal hours or h:mm:ss
+fn parse_hours(text: &str) -> Option<f64> {
+    if !text.contains(':') {
+        return text.parse().ok();
+    }
+
+    let parts: Vec<f64> = text.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [h, m] => Some(h + m / 60.0),
+        [h, m, s] => Some(h + m / 60.0 + s / 3600.0),
+        _ => None,
+    }
+}
+
+fn parse_bool(text: &str) -> bool {
+    matches!(text.to_lowercase().as_str(), "true" | "yes" | "y" | "x" | "1" | "done")
+}
+
+// Local times as written on export, RFC 3339 or dates alone
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = NaiveDateTime::parse_from_str(text, DATE_TIME_FORMAT) {
+        return Local.from_local_datetime(&time).earliest().map(|t| t.with_timezone(&Utc));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(text, DATE_FORMAT).ok().map(start_of_day)
+}
+
+// The delimiter that appears most in the header
+fn find_delimiter(text: &str) -> u8 {
+    let header = text.lines().next().unwrap_or("");
+
+    DELIMITERS
+        .iter()
+        .copied()
+        .max_by_key(|d| header.bytes().filter(|b| b == d).count())
+        .filter(|d| header.as_bytes().contains(d))
+        .unwrap_or(b',')
+}
@@ -0,0 +1,131 @@
+// ----------------------------------------------------------------------------
+// ICAL SUB-MODULE
+// This submodule writes an iCalendar file for calendar apps. The tasks of the
+// list are VTODO entries and every session of tracked time, archived tasks
+// included, is a VEVENT so the calendar shows when the work happened. Tasks
+// from before sessions were kept only have totals, which have no time of day.
+// ----------------------------------------------------------------------------
+
+use super::{split_tags, Data};
+use super::super::ArchiveItem;
+use super::super::task::{Session, Task};
+
+use chrono::{DateTime, Utc};
+
+// ---- CONSTANTS ----
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const DATE_FORMAT: &str = "%Y%m%d";
+
+// Longer lines are folded, as the spec asks
+const MAX_LINE_BYTES: usize = 75;
+
+pub fn write(data: &Data) -> String {
+    calendar(&data.tasks, &data.archive)
+}
+
+pub fn calendar(tasks: &[Task], archive: &[ArchiveItem]) -> String {
+    let stamp = Utc::now().format(TIME_FORMAT).to_string();
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//todo_rust//todo_rust//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+
+    for task in tasks {
+        push_todo(&mut lines, task, &stamp);
+    }
+
+    for task in tasks.iter().chain(archive.iter().flat_map(|a| a.tasks.iter())) {
+        for session in &task.sessions {
+            push_event(&mut lines, task, session, &stamp);
+        }
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    let mut text = String::new();
+    for line in lines {
+        text.push_str(&fold(&line));
+    }
+
+    text
+}
+
+fn push_todo(lines: &mut Vec<String>, task: &Task, stamp: &str) {
+    let (_, tags) = split_tags(&task.title);
+
+    lines.push(String::from("BEGIN:VTODO"));
+    lines.push(format!("UID:{}", task.id));
+    lines.push(format!("DTSTAMP:{}", stamp));
+    lines.push(format!("CREATED:{}", task.created_on.format(TIME_FORMAT)));
+    lines.push(format!("SUMMARY:{}", escape(&task.title)));
+    if !task.description.is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape(&task.description)));
+    }
+    if let Some(due) = task.due {
+        lines.push(format!("DUE;VALUE=DATE:{}", due.format(DATE_FORMAT)));
+    }
+    if let Some(priority) = task.priority {
+        // 1 is the highest, 5 medium and 9 the lowest
+        lines.push(format!("PRIORITY:{}", match priority {
+            'A' => 1,
+            'B' => 5,
+            _ => 9,
+        }));
+    }
+    if !tags.is_empty() {
+        let tags: Vec<String> = tags.iter().map(|t| escape(t)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    if task.is_done {
+        lines.push(String::from("STATUS:COMPLETED"));
+        lines.push(String::from("PERCENT-COMPLETE:100"));
+    } else if task.is_active {
+        lines.push(String::from("STATUS:IN-PROCESS"));
+    } else {
+        lines.push(String::from("STATUS:NEEDS-ACTION"));
+    }
+    lines.push(String::from("END:VTODO"));
+}
+
+fn push_event(lines: &mut Vec<String>, task: &Task, session: &Session, stamp: &str) {
+    lines.push(String::from("BEGIN:VEVENT"));
+    lines.push(format!("UID:{}-{}", task.id, session.start.format(TIME_FORMAT)));
+    lines.push(format!("DTSTAMP:{}", stamp));
+    lines.push(format!("DTSTART:{}", utc_time(session.start)));
+    lines.push(format!("DTEND:{}", utc_time(session.end)));
+    lines.push(format!("SUMMARY:{}", escape(task.title.lines().next().unwrap_or(""))));
+    lines.push(format!("RELATED-TO:{}", task.id));
+    lines.push(String::from("END:VEVENT"));
+}
+
+fn utc_time(time: DateTime<Utc>) -> String {
+    time.format(TIME_FORMAT).to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Splits a line into CRLF ended parts, the ones after the first starting with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_BYTES {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
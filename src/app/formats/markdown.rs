@@ -0,0 +1,252 @@
+// ----------------------------------------------------------------------------
+// MARKDOWN SUB-MODULE
+// This submodule reads GitHub task lists and writes the tasks and the archive
+// as Markdown with checkboxes, for notes and reports:
+//   - [x] Title _(1 h 5 min, created 2024/03/01)_
+//     Description, indented under the task
+// On import every top level item becomes a task, and whatever is indented
+// under it, nested items included, becomes its description. Done items under
+// the "# Archive" heading go back to the archive, on the day of the heading
+// above them. Tasks get ids made from their title and where they are in the
+// file, so importing the same file again doesn't add them twice.
+// ----------------------------------------------------------------------------
+
+use super::{archive_from, local_date, start_of_day, Data};
+use super::super::task::{duration_to_string, Task};
+
+use crate::error::Result;
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+const DATE_FORMAT: &str = "%Y/%m/%d";
+
+// Items need two spaces more than the one above to be nested in it
+const NESTED_INDENT: usize = 2;
+
+pub fn write(data: &Data) -> String {
+    let mut text = String::from("# To Do\n\n");
+    for task in &data.tasks {
+        push_task(&mut text, task);
+    }
+
+    if !data.archive.is_empty() {
+        text.push_str("\n# Archive\n");
+        for item in &data.archive {
+            text.push_str(&format!("\n## {}\n\n", local_date(item.date).format(DATE_FORMAT)));
+            for task in &item.tasks {
+                push_task(&mut text, task);
+            }
+        }
+    }
+
+    text
+}
+
+pub fn read(text: &str) -> Result<Data> {
+    // Tasks in the order of the file, with the day they were archived on
+    let mut tasks: Vec<(Option<DateTime<Utc>>, Task)> = vec![];
+    // Blank lines are only kept if the description goes on after them
+    let mut blank_lines = 0;
+    let mut in_task = false;
+    let mut in_archive = false;
+    let mut archive_date = start_of_day(Local::now().date_naive());
+    let mut seen = HashMap::new();
+
+    for line in text.lines() {
+        let indent = indent_of(line);
+
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+
+        if indent < NESTED_INDENT {
+            if let Some((is_done, title)) = parse_item(line.trim_start()) {
+                let mut task = Task { is_done, ..Task::default() };
+                set_title(&mut task, title);
+
+                let archived = (in_archive && is_done).then_some(archive_date);
+                task.id = stable_id(&task.title, archived, &mut seen);
+                tasks.push((archived, task));
+                in_task = true;
+            } else {
+                // Headings and paragraphs end the task above them
+                if let Some(heading) = line.trim_start().strip_prefix("# ") {
+                    in_archive = heading.trim().eq_ignore_ascii_case("archive");
+                } else if let Some(date) = line.trim_start().strip_prefix("## ").and_then(|d| parse_date(d.trim())) {
+                    archive_date = start_of_day(date);
+                }
+                in_task = false;
+            }
+        } else if in_task {
+            if let Some((_, task)) = tasks.last_mut() {
+                if !task.description.is_empty() {
+                    task.description.push_str(&"\n".repeat(blank_lines + 1));
+                }
+                task.description.push_str(strip_indent(line, NESTED_INDENT));
+            }
+        }
+
+        blank_lines = 0;
+    }
+
+    let mut data = Data::default();
+    let mut archived = vec![];
+    for (date, task) in tasks {
+        match date {
+            Some(date) => archived.push((date, task)),
+            None => data.tasks.push(task),
+        }
+    }
+    data.archive = archive_from(archived);
+
+    Ok(data)
+}
+
+// Made from the title and the list or archive day the task is in. Tasks alike
+// are told apart by how many came before them.
+fn stable_id(title: &str, archived: Option<DateTime<Utc>>, seen: &mut HashMap<String, usize>) -> Uuid {
+    let list = archived.map(|date| date.to_rfc3339()).unwrap_or_default();
+    let name = format!("{}\n{}", list, title);
+
+    let count = seen.entry(name.clone()).or_insert(0);
+    let name = if *count == 0 { name } else { format!("{}\n{}", name, count) };
+    *count += 1;
+
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes())
+}
+
+fn push_task(text: &mut String, task: &Task) {
+    let mut lines = task.title.lines();
+    let first = lines.next().unwrap_or("");
+
+    let mut meta = vec![];
+    if !task.elapsed_time.is_zero() {
+        meta.push(duration_to_string(task.elapsed_time).trim().to_string());
+    }
+    if let Some(due) = task.due {
+        meta.push(format!("due {}", due.format(DATE_FORMAT)));
+    }
+    meta.push(format!("created {}", local_date(task.created_on).format(DATE_FORMAT)));
+
+    text.push_str(&format!("- [{}] {} _({})_\n", if task.is_done { "x" } else { " " }, first, meta.join(", ")));
+
+    // The rest of a title with more than one line goes with the description
+    for line in lines.chain(task.description.lines()) {
+        if line.is_empty() {
+            text.push('\n');
+        } else {
+            text.push_str(&format!("  {}\n", line));
+        }
+    }
+}
+
+// "- [ ] Title", with any list marker, returns whether it is done and the title
+fn parse_item(line: &str) -> Option<(bool, &str)> {
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .or_else(|| {
+            let (number, rest) = line.split_once(". ").or_else(|| line.split_once(") "))?;
+            number.chars().all(|c| c.is_ascii_digit()).then_some(rest)
+        })?;
+
+    let rest = rest.trim_start();
+    let is_done = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+
+    Some((is_done, rest[3..].trim()))
+}
+
+// Takes the dates back from the notes written on export
+fn set_title(task: &mut Task, title: &str) {
+    task.title = title.to_string();
+
+    if let Some((text, meta)) = title.strip_suffix(")_").and_then(|t| t.rsplit_once(" _(")) {
+        task.title = text.to_string();
+        for part in meta.split(", ") {
+            if let Some(date) = part.strip_prefix("created ").and_then(parse_date) {
+                task.created_on = start_of_day(date);
+            } else if let Some(date) = part.strip_prefix("due ").and_then(parse_date) {
+                task.due = Some(date);
+            }
+        }
+    }
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, DATE_FORMAT)
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y-%m-%d"))
+        .ok()
+}
+
+fn indent_of(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+// Removes up to the given number of columns of indentation
+fn strip_indent(line: &str, columns: usize) -> &str {
+    let mut removed = 0;
+    for (index, c) in line.char_indices() {
+        if removed >= columns || !c.is_whitespace() {
+            return &line[index..];
+        }
+        removed += if c == '\t' { 4 } else { 1 };
+    }
+
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::task::test_task;
+    use crate::app::ArchiveItem;
+
+    fn ids(tasks: &[Task]) -> Vec<Uuid> {
+        tasks.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn reading_a_file_again_gives_the_same_ids() {
+        let text = "# To Do\n\n- [ ] Write\n- [ ] Write\n- [x] Read\n";
+
+        let first = read(text).unwrap();
+        let again = read(text).unwrap();
+
+        assert_eq!(ids(&first.tasks), ids(&again.tasks));
+        // Tasks alike still have ids of their own
+        assert_ne!(first.tasks[0].id, first.tasks[1].id);
+    }
+
+    #[test]
+    fn done_tasks_of_the_archive_go_back_to_it() {
+        let mut done = test_task("Old");
+        done.is_done = true;
+        let date = start_of_day(NaiveDate::from_ymd_opt(2024, 3, 2).unwrap());
+        let data = Data {
+            tasks: vec![test_task("New")],
+            archive: vec![ArchiveItem { date, tasks: vec![done] }],
+        };
+
+        let read_back = read(&write(&data)).unwrap();
+
+        assert_eq!(read_back.tasks.len(), 1);
+        assert_eq!(read_back.tasks[0].title, "New");
+        assert_eq!(read_back.archive.len(), 1);
+        assert_eq!(read_back.archive[0].date, date);
+        assert_eq!(read_back.archive[0].tasks[0].title, "Old");
+        assert_ne!(read_back.archive[0].tasks[0].id, read_back.tasks[0].id);
+    }
+}
@@ -0,0 +1,316 @@
+// ----------------------------------------------------------------------------
+// ORG SUB-MODULE
+// This submodule writes the tasks and the archive as org-mode headings and
+// reads TODO and DONE headings back:
+//   ** TODO [#A] Title :tag:
+//      DEADLINE: <2024-03-10 Sun>
+//      :PROPERTIES:
+//      :ID:       uuid
+//      :CREATED:  [2024-03-01 Fri 10:15]
+//      :END:
+//      :LOGBOOK:
+//      CLOCK: [2024-03-01 Fri 10:15]--[2024-03-01 Fri 11:15] =>  1:00
+//      :END:
+//      Description
+// Every tracked session is a CLOCK line, time tracked before sessions were
+// kept is a single one from the creation time. Archived tasks are closed on
+// the day of their batch and DONE headings with CLOSED go back to the archive.
+// ----------------------------------------------------------------------------
+
+use super::{archive_from, join_tags, local_date, split_tags, start_of_day, Data};
+use super::super::task::{Session, Task};
+
+use crate::error::Result;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+const STAMP_FORMAT: &str = "%Y-%m-%d %a %H:%M";
+const DATE_FORMAT: &str = "%Y-%m-%d %a";
+
+pub fn write(data: &Data) -> String {
+    let mut text = String::from("#+TITLE: todo_rust\n\n* Tasks\n");
+    for task in &data.tasks {
+        push_task(&mut text, task, None);
+    }
+
+    if !data.archive.is_empty() {
+        text.push_str("* Archive\n");
+        for item in &data.archive {
+            text.push_str(&format!("** {}\n", local_date(item.date).format("%Y-%m-%d")));
+            for task in &item.tasks {
+                push_task(&mut text, task, Some(item.date));
+            }
+        }
+    }
+
+    text
+}
+
+pub fn read(text: &str) -> Result<Data> {
+    let mut data = Data::default();
+    let mut archived = vec![];
+    let mut current: Option<Heading> = None;
+
+    for line in text.lines() {
+        if line.starts_with('*') {
+            if let Some(heading) = current.take() {
+                heading.finish(&mut data, &mut archived);
+            }
+            current = Heading::parse(line);
+        } else if let Some(heading) = &mut current {
+            heading.add_line(line);
+        }
+    }
+
+    if let Some(heading) = current.take() {
+        heading.finish(&mut data, &mut archived);
+    }
+
+    data.archive = archive_from(archived);
+
+    Ok(data)
+}
+
+fn push_task(text: &mut String, task: &Task, archived: Option<DateTime<Utc>>) {
+    let level = if archived.is_some() { "***" } else { "**" };
+    let keyword = if task.is_done || archived.is_some() { "DONE" } else { "TODO" };
+    let (title, tags) = split_tags(task.title.lines().next().unwrap_or(""));
+
+    let mut heading = format!("{} {}", level, keyword);
+    if let Some(priority) = task.priority {
+        heading.push_str(&format!(" [#{}]", priority));
+    }
+    heading.push(' ');
+    heading.push_str(&title);
+    if !tags.is_empty() {
+        heading.push_str(&format!(" :{}:", tags.join(":")));
+    }
+    text.push_str(&heading);
+    text.push('\n');
+
+    let indent = " ".repeat(level.len() + 1);
+
+    let mut planning = vec![];
+    if let Some(date) = archived {
+        planning.push(format!("CLOSED: [{}]", local_stamp(date)));
+    }
+    if let Some(due) = task.due {
+        planning.push(format!("DEADLINE: <{}>", due.format(DATE_FORMAT)));
+    }
+    if !planning.is_empty() {
+        text.push_str(&format!("{}{}\n", indent, planning.join(" ")));
+    }
+
+    text.push_str(&format!("{}:PROPERTIES:\n", indent));
+    text.push_str(&format!("{}:ID:       {}\n", indent, task.id));
+    text.push_str(&format!("{}:CREATED:  [{}]\n", indent, local_stamp(task.created_on)));
+    text.push_str(&format!("{}:END:\n", indent));
+
+    // Time tracked before sessions were kept goes in one from the creation time
+    let mut clocks = task.sessions.clone();
+    let in_sessions = task.sessions.iter().fold(chrono::Duration::zero(), |sum, s| sum + (s.end - s.start));
+    let untracked = chrono::Duration::from_std(task.elapsed_time).unwrap_or_else(|_| chrono::Duration::zero()) - in_sessions;
+    if untracked >= chrono::Duration::seconds(1) {
+        clocks.insert(0, Session { start: task.created_on, end: task.created_on + untracked });
+    }
+
+    if !clocks.is_empty() {
+        text.push_str(&format!("{}:LOGBOOK:\n", indent));
+        for session in clocks.iter().rev() {
+            // Counted between the minutes shown, as org does
+            let minutes = session.end.timestamp() / 60 - session.start.timestamp() / 60;
+            text.push_str(&format!(
+                "{}CLOCK: [{}]--[{}] => {:2}:{:02}\n",
+                indent, local_stamp(session.start), local_stamp(session.end), minutes / 60, minutes % 60,
+            ));
+        }
+        text.push_str(&format!("{}:END:\n", indent));
+    }
+
+    // The rest of a title with more than one line goes with the description
+    for line in task.title.lines().skip(1).chain(task.description.lines()) {
+        if line.is_empty() {
+            text.push('\n');
+        } else if line.starts_with('*') {
+            // Would be read as a heading, org escapes it with a comma
+            text.push_str(&format!("{},{}\n", indent, line));
+        } else {
+            text.push_str(&format!("{}{}\n", indent, line));
+        }
+    }
+}
+
+// A TODO or DONE heading and the lines under it
+struct Heading {
+    task: Task,
+    closed: Option<DateTime<Utc>>,
+    in_drawer: Option<String>,
+    body: Vec<String>,
+}
+
+impl Heading {
+    fn parse(line: &str) -> Option<Heading> {
+        let rest = line.trim_start_matches('*').strip_prefix(' ')?.trim();
+        let (keyword, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        let is_done = match keyword {
+            "TODO" => false,
+            "DONE" => true,
+            _ => return None,
+        };
+
+        let mut rest = rest.trim();
+        let mut priority = None;
+        if let Some(after) = rest.strip_prefix("[#") {
+            let mut chars = after.chars();
+            if let (Some(p), Some(']')) = (chars.next(), chars.next()) {
+                priority = Some(p).filter(char::is_ascii_uppercase);
+                rest = chars.as_str().trim_start();
+            }
+        }
+
+        // Tags are the last word, like :a:b:
+        let mut title = rest.to_string();
+        let mut tags = vec![];
+        if let Some((start, last)) = rest.rsplit_once(' ') {
+            if last.len() > 2 && last.starts_with(':') && last.ends_with(':') {
+                tags = last.trim_matches(':').split(':').filter(|t| !t.is_empty()).map(String::from).collect();
+                title = start.trim_end().to_string();
+            }
+        }
+
+        Some(Heading {
+            task: Task {
+                title: join_tags(&title, &tags),
+                is_done,
+                priority,
+                ..Task::default()
+            },
+            closed: None,
+            in_drawer: None,
+            body: vec![],
+        })
+    }
+
+    fn add_line(&mut self, line: &str) {
+        let trimmed = line.trim();
+
+        if let Some(drawer) = &self.in_drawer {
+            if trimmed == ":END:" {
+                self.in_drawer = None;
+            } else if drawer == "PROPERTIES" {
+                self.add_property(trimmed);
+            } else if drawer == "LOGBOOK" {
+                if let Some(session) = trimmed.strip_prefix("CLOCK:").and_then(parse_clock) {
+                    self.task.sessions.push(session);
+                }
+            }
+            return;
+        }
+
+        if trimmed.len() > 2 && trimmed.starts_with(':') && trimmed.ends_with(':') && !trimmed.contains(' ') {
+            self.in_drawer = Some(trimmed.trim_matches(':').to_uppercase());
+        } else if trimmed.starts_with("CLOSED:") || trimmed.starts_with("DEADLINE:") || trimmed.starts_with("SCHEDULED:") {
+            self.add_planning(trimmed);
+        } else {
+            self.body.push(line.to_string());
+        }
+    }
+
+    fn add_property(&mut self, line: &str) {
+        let Some((key, value)) = line.strip_prefix(':').and_then(|l| l.split_once(':')) else {
+            return;
+        };
+
+        match key.to_uppercase().as_str() {
+            "ID" => if let Ok(id) = Uuid::parse_str(value.trim()) {
+                self.task.id = id;
+            },
+            "CREATED" => if let Some(time) = parse_stamp(value.trim()) {
+                self.task.created_on = time;
+            },
+            _ => {},
+        }
+    }
+
+    fn add_planning(&mut self, line: &str) {
+        for (keyword, rest) in [("CLOSED:", "]"), ("DEADLINE:", ">")] {
+            let Some(start) = line.find(keyword) else {
+                continue;
+            };
+            let value = line[start + keyword.len()..].trim_start();
+            let Some(end) = value.find(rest) else {
+                continue;
+            };
+            let stamp = &value[..=end];
+
+            if keyword == "CLOSED:" {
+                self.closed = parse_stamp(stamp);
+            } else {
+                self.task.due = parse_stamp(stamp).map(local_date);
+            }
+        }
+    }
+
+    fn finish(mut self, data: &mut Data, archived: &mut Vec<(DateTime<Utc>, Task)>) {
+        // Descriptions lose the indentation of the heading and blank lines around them
+        let indent = self.body
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines: Vec<String> = self.body
+            .iter()
+            .map(|l| {
+                let line = l.get(indent..).unwrap_or("").trim_end_matches('\r');
+                line.strip_prefix(",*").map(|rest| format!("*{}", rest)).unwrap_or_else(|| line.to_string())
+            })
+            .collect();
+        self.task.description = lines.join("\n").trim_matches('\n').to_string();
+
+        self.task.sessions.sort_by_key(|s| s.start);
+        for session in &self.task.sessions {
+            let time = (session.end - session.start).to_std().unwrap_or_default();
+            self.task.elapsed_time += time;
+            *self.task.daily_time.entry(local_date(session.start)).or_default() += time;
+        }
+
+        match self.closed {
+            Some(date) if self.task.is_done => archived.push((date, self.task)),
+            _ => data.tasks.push(self.task),
+        }
+    }
+}
+
+fn local_stamp(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format(STAMP_FORMAT).to_string()
+}
+
+// [2024-03-01 Fri 10:15] or <2024-03-01 Fri>, the day name and time are optional
+fn parse_stamp(stamp: &str) -> Option<DateTime<Utc>> {
+    let inner = stamp.trim().trim_start_matches(['[', '<']).trim_end_matches([']', '>']);
+    let mut words = inner.split_whitespace();
+    let date = NaiveDate::parse_from_str(words.next()?, "%Y-%m-%d").ok()?;
+
+    let time = words.find(|w| w.contains(':'));
+    match time {
+        Some(time) => {
+            let time = NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").ok()?;
+            Local.from_local_datetime(&time).earliest().map(|t| t.with_timezone(&Utc))
+        },
+        None => Some(start_of_day(date)),
+    }
+}
+
+// [start]--[end] => h:mm
+fn parse_clock(text: &str) -> Option<Session> {
+    let (start, rest) = text.trim().split_once("--")?;
+    let end = rest.split("=>").next()?;
+
+    let start = parse_stamp(start)?;
+    let end = parse_stamp(end)?;
+    (end >= start).then_some(Session { start, end })
+}
@@ -0,0 +1,167 @@
+// ----------------------------------------------------------------------------
+// TASKWARRIOR SUB-MODULE
+// This submodule reads the JSON of task export and writes JSON for task
+// import. The title is the description without its +tags, the description is
+// kept as an annotation and the tracked time goes in two UDAs:
+//   tracked       tracked time in seconds, numeric
+//   trackeddays   tracked time per day, as day=seconds separated by commas
+// Pending tasks go to the list and completed ones to the archive on the day
+// they were completed. Deleted and recurring parent tasks are skipped.
+// ----------------------------------------------------------------------------
+
+use super::{archive_from, days_to_string, join_tags, local_date, parse_days, split_tags, start_of_day, Data};
+use super::super::task::Task;
+
+use crate::error::Result;
+
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+
+use serde::{Deserialize, Serialize};
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct TwTask {
+    uuid: String,
+    status: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tracked: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trackeddays: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Annotation {
+    entry: String,
+    description: String,
+}
+
+pub fn write(data: &Data) -> Result<String> {
+    let now = Utc::now();
+    let mut exported: Vec<TwTask> = data.tasks
+        .iter()
+        .map(|task| to_taskwarrior(task, if task.is_done { Some(now) } else { None }))
+        .collect();
+
+    for item in &data.archive {
+        exported.extend(item.tasks.iter().map(|task| to_taskwarrior(task, Some(item.date))));
+    }
+
+    Ok(serde_json::to_string_pretty(&exported)? + "\n")
+}
+
+// Takes the JSON array of task export, or one task per line
+pub fn read(text: &str) -> Result<Data> {
+    let imported: Vec<TwTask> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text)?
+    } else {
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line.trim().trim_end_matches(',')))
+            .collect::<serde_json::Result<_>>()?
+    };
+
+    let mut data = Data::default();
+    let mut archived = vec![];
+
+    for tw_task in imported {
+        let status = tw_task.status.clone();
+        let end = tw_task.end.as_deref().and_then(parse_time);
+        let task = from_taskwarrior(tw_task);
+
+        match status.as_str() {
+            "pending" | "waiting" => data.tasks.push(task),
+            "completed" => archived.push((end.unwrap_or(task.created_on), task)),
+            _ => {},
+        }
+    }
+
+    data.archive = archive_from(archived);
+
+    Ok(data)
+}
+
+fn to_taskwarrior(task: &Task, completed: Option<DateTime<Utc>>) -> TwTask {
+    let (description, tags) = split_tags(&task.title);
+
+    let annotations = if task.description.is_empty() {
+        vec![]
+    } else {
+        vec![Annotation {
+            entry: task.created_on.format(TIME_FORMAT).to_string(),
+            description: task.description.clone(),
+        }]
+    };
+
+    TwTask {
+        uuid: task.id.to_string(),
+        status: String::from(if completed.is_some() { "completed" } else { "pending" }),
+        description,
+        entry: Some(task.created_on.format(TIME_FORMAT).to_string()),
+        modified: Some(Utc::now().format(TIME_FORMAT).to_string()),
+        end: completed.map(|date| date.format(TIME_FORMAT).to_string()),
+        due: task.due.map(|due| start_of_day(due).format(TIME_FORMAT).to_string()),
+        priority: task.priority.map(|p| String::from(match p {
+            'A' => "H",
+            'B' => "M",
+            _ => "L",
+        })),
+        tags,
+        annotations,
+        tracked: if task.elapsed_time.is_zero() { None } else { Some(task.elapsed_time.as_secs()) },
+        trackeddays: if task.daily_time.is_empty() { None } else { Some(days_to_string(&task.daily_time)) },
+    }
+}
+
+fn from_taskwarrior(tw_task: TwTask) -> Task {
+    let daily_time = tw_task.trackeddays.as_deref().and_then(parse_days).unwrap_or_default();
+
+    let notes: Vec<String> = tw_task.annotations.into_iter().map(|a| a.description).collect();
+
+    Task {
+        id: Uuid::parse_str(&tw_task.uuid).unwrap_or_else(|_| Uuid::new_v4()),
+        title: join_tags(&tw_task.description, &tw_task.tags),
+        description: notes.join("\n"),
+        is_done: tw_task.status == "completed",
+        elapsed_time: tw_task.tracked.map(Duration::from_secs).unwrap_or_else(|| daily_time.values().sum()),
+        created_on: tw_task.entry.as_deref().and_then(parse_time).unwrap_or_else(Utc::now),
+        priority: tw_task.priority.as_deref().and_then(|p| match p {
+            "H" => Some('A'),
+            "M" => Some('B'),
+            "L" => Some('C'),
+            _ => None,
+        }),
+        due: tw_task.due.as_deref().and_then(parse_time).map(local_date),
+        daily_time,
+        ..Task::default()
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, TIME_FORMAT)
+        .ok()
+        .map(|t| Utc.from_utc_datetime(&t))
+        .or_else(|| DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc)))
+}
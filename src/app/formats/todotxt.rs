@@ -0,0 +1,385 @@
+// ----------------------------------------------------------------------------
+// TODO.TXT SUB-MODULE
+// This submodule reads and writes the todo.txt format, one task per line:
+//   x 2024-03-02 2024-03-01 Title +project @context key:value
+// Projects and contexts stay in the title. What todo.txt has no place for is
+// kept in key:value extensions, so an exported file is read back as it was:
+//   uuid:         id of the task
+//   due:          due date
+//   pri:A         priority of a done task, open tasks have it as (A)
+//   created:      creation time, the date alone is in front of the title
+//   archived:     time the task was archived, the completion date is its day
+//   time:         tracked time in seconds
+//   spent:        tracked time per day, as day=seconds separated by commas
+//   sessions:     when the time was tracked, as start/end separated by commas
+//   title:        whole title when it has more than one line or words that
+//                 would be read as one of these keys
+//   desc:         description, spaces, newlines and % are written as %XX
+// Done tasks of other tools with a completion date go to the archive on that
+// day, those written here have the archived key when they were archived.
+// ----------------------------------------------------------------------------
+
+use super::{archive_from, days_to_string, local_date, parse_days, parse_secs, secs_to_string, start_of_day, Data};
+use super::super::task::{Session, Task};
+
+use crate::error::Result;
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+const KEYS: &[&str] = &["uuid", "due", "pri", "created", "archived", "time", "spent", "sessions", "title", "desc"];
+
+pub fn write(data: &Data) -> String {
+    let mut text = String::new();
+
+    for task in &data.tasks {
+        text.push_str(&task_line(task, None));
+        text.push('\n');
+    }
+
+    for item in &data.archive {
+        for task in &item.tasks {
+            text.push_str(&task_line(task, Some(item.date)));
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+pub fn read(text: &str) -> Result<Data> {
+    let mut data = Data::default();
+    let mut archived = vec![];
+
+    for line in text.lines().map(|l| l.trim_end_matches('\r')) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (task, archive_date) = parse_line(line);
+        match archive_date {
+            Some(date) => archived.push((date, task)),
+            None => data.tasks.push(task),
+        }
+    }
+
+    data.archive = archive_from(archived);
+
+    Ok(data)
+}
+
+fn task_line(task: &Task, archived: Option<DateTime<Utc>>) -> String {
+    let mut words: Vec<String> = vec![];
+    let created = local_date(task.created_on).format(DATE_FORMAT).to_string();
+
+    if let Some(date) = archived {
+        // Both dates are needed for the second one to be the creation date
+        words.push(String::from("x"));
+        words.push(local_date(date).format(DATE_FORMAT).to_string());
+        words.push(created);
+    } else if task.is_done {
+        // There is no completion time, the end of the last session stands for it
+        let completed = task.sessions.last().map_or(task.created_on, |s| s.end);
+        words.push(String::from("x"));
+        words.push(local_date(completed).format(DATE_FORMAT).to_string());
+        words.push(created);
+    } else {
+        if let Some(priority) = task.priority {
+            words.push(format!("({})", priority));
+        }
+        words.push(created);
+    }
+
+    // The line keeps the first line of the title so other tools show it, words
+    // that would be read as keys are escaped there and kept in the title key
+    let shown: Vec<String> = task.title
+        .lines()
+        .next()
+        .unwrap_or("")
+        .split(' ')
+        .map(|word| if is_extension(word) { word.replacen(':', "%3A", 1) } else { word.to_string() })
+        .collect();
+    words.push(shown.join(" "));
+
+    words.push(format!("uuid:{}", task.id));
+    if let Some(due) = task.due {
+        words.push(format!("due:{}", due.format(DATE_FORMAT)));
+    }
+    if task.priority.is_some() && (task.is_done || archived.is_some()) {
+        words.push(format!("pri:{}", task.priority.unwrap_or('A')));
+    }
+    words.push(format!("created:{}", task.created_on.to_rfc3339_opts(SecondsFormat::AutoSi, true)));
+    if let Some(date) = archived {
+        words.push(format!("archived:{}", date.to_rfc3339_opts(SecondsFormat::AutoSi, true)));
+    }
+    if !task.elapsed_time.is_zero() {
+        words.push(format!("time:{}", secs_to_string(task.elapsed_time)));
+    }
+    if !task.daily_time.is_empty() {
+        words.push(format!("spent:{}", days_to_string(&task.daily_time)));
+    }
+    if !task.sessions.is_empty() {
+        let sessions: Vec<String> = task.sessions
+            .iter()
+            .map(|s| format!("{}/{}", s.start.to_rfc3339_opts(SecondsFormat::AutoSi, true), s.end.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+            .collect();
+        words.push(format!("sessions:{}", sessions.join(",")));
+    }
+    if task.title.contains(['\n', '\r']) || task.title.split(' ').any(is_extension) {
+        words.push(format!("title:{}", encode(&task.title)));
+    }
+    if !task.description.is_empty() {
+        words.push(format!("desc:{}", encode(&task.description)));
+    }
+
+    words.join(" ")
+}
+
+// Returns the task and the time it was archived, if it was
+fn parse_line(line: &str) -> (Task, Option<DateTime<Utc>>) {
+    let mut task = Task::default();
+    let mut rest = line;
+    let mut completed_on = None;
+    let mut created_day = None;
+
+    if let Some(after) = rest.strip_prefix("x ") {
+        task.is_done = true;
+        rest = after;
+        if let Some((date, after)) = take_date(rest) {
+            completed_on = Some(date);
+            rest = after;
+        }
+    }
+
+    if let Some((priority, after)) = take_priority(rest) {
+        task.priority = Some(priority);
+        rest = after;
+    }
+
+    if let Some((date, after)) = take_date(rest) {
+        created_day = Some(date);
+        rest = after;
+    }
+
+    let mut created_on = None;
+    let mut archived_on = None;
+    let mut elapsed_time = None;
+    let mut full_title = None;
+    let mut title_words = vec![];
+
+    // Extensions that can't be read are left in the title
+    for word in rest.split(' ') {
+        let parsed = match word.split_once(':') {
+            Some(("pri", value)) => take_priority(&format!("({}) ", value)).map(|(p, _)| task.priority = Some(p)),
+            Some(("uuid", value)) => Uuid::parse_str(value).ok().map(|id| task.id = id),
+            Some(("due", value)) => NaiveDate::parse_from_str(value, DATE_FORMAT).ok().map(|d| task.due = Some(d)),
+            Some(("created", value)) => parse_time(value).map(|t| created_on = Some(t)),
+            Some(("archived", value)) => parse_time(value).map(|t| archived_on = Some(t)),
+            Some(("time", value)) => parse_secs(value).map(|t| elapsed_time = Some(t)),
+            Some(("spent", value)) => parse_days(value).map(|days| task.daily_time = days),
+            Some(("sessions", value)) => parse_sessions(value).map(|sessions| task.sessions = sessions),
+            Some(("title", value)) => {
+                full_title = Some(decode(value));
+                Some(())
+            },
+            Some(("desc", value)) => {
+                task.description = decode(value);
+                Some(())
+            },
+            _ => None,
+        };
+
+        if parsed.is_none() {
+            title_words.push(word);
+        }
+    }
+
+    task.title = full_title.unwrap_or_else(|| title_words.join(" "));
+    task.created_on = created_on
+        .or_else(|| created_day.map(start_of_day))
+        .or_else(|| completed_on.map(start_of_day))
+        .unwrap_or_else(Utc::now);
+    task.elapsed_time = elapsed_time.unwrap_or_else(|| task.daily_time.values().sum());
+
+    // Lines written here say when they were archived
+    let written_here = created_on.is_some();
+    let archived = archived_on.or_else(|| completed_on.filter(|_| !written_here).map(start_of_day));
+    if archived.is_some() {
+        task.is_done = true;
+    }
+
+    (task, archived)
+}
+
+// A word that would be read as an extension
+fn is_extension(word: &str) -> bool {
+    word.split_once(':').is_some_and(|(key, _)| KEYS.contains(&key))
+}
+
+// "(A) " at the start of the text
+fn take_priority(text: &str) -> Option<(char, &str)> {
+    let bytes = text.as_bytes();
+    if bytes.len() >= 4 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')' && bytes[3] == b' ' {
+        Some((bytes[1] as char, &text[4..]))
+    } else {
+        None
+    }
+}
+
+// A date and a space at the start of the text
+fn take_date(text: &str) -> Option<(NaiveDate, &str)> {
+    let date = NaiveDate::parse_from_str(text.get(..10)?, DATE_FORMAT).ok()?;
+    let rest = text[10..].strip_prefix(' ')?;
+
+    Some((date, rest))
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc))
+}
+
+fn parse_sessions(value: &str) -> Option<Vec<Session>> {
+    value
+        .split(',')
+        .map(|session| {
+            let (start, end) = session.split_once('/')?;
+            Some(Session { start: parse_time(start)?, end: parse_time(end)? })
+        })
+        .collect()
+}
+
+// Characters that would break the line into words, and % itself
+fn encode(text: &str) -> String {
+    let mut encoded = String::new();
+
+    for c in text.chars() {
+        match c {
+            '%' | ' ' | '\n' | '\r' | '\t' => encoded.push_str(&format!("%{:02X}", c as u32)),
+            c => encoded.push(c),
+        }
+    }
+
+    encoded
+}
+
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escaped = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            },
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ArchiveItem;
+    use crate::app::task::test_task;
+
+    use chrono::TimeZone;
+
+    // Writes the tasks as a list and reads them back
+    fn round_trip(tasks: Vec<Task>) -> Data {
+        read(&write(&Data { tasks, archive: vec![] })).unwrap()
+    }
+
+    fn assert_same(read: &Task, written: &Task) {
+        assert_eq!(read.id, written.id);
+        assert_eq!(read.title, written.title);
+        assert_eq!(read.is_done, written.is_done);
+        assert_eq!(read.priority, written.priority);
+        assert_eq!(read.due, written.due);
+        assert_eq!(read.created_on, written.created_on);
+        assert_eq!(read.elapsed_time, written.elapsed_time);
+    }
+
+    #[test]
+    fn title_words_shaped_like_keys_stay_in_the_title() {
+        let written = vec![
+            test_task("Ask about pri:A and due:2024-01-01"),
+            test_task("Log time:90 for created:today"),
+            test_task(&format!("Copy uuid:{} and title:x desc:y", Uuid::new_v4())),
+        ];
+
+        let data = round_trip(written.clone());
+
+        assert_eq!(data.tasks.len(), written.len());
+        for (read, written) in data.tasks.iter().zip(&written) {
+            assert_same(read, written);
+        }
+    }
+
+    #[test]
+    fn done_tasks_stay_in_the_list() {
+        let written = vec![
+            Task { is_done: true, ..test_task("2024-01-01 review") },
+            Task { is_done: true, priority: Some('B'), ..test_task("(A) call back") },
+            Task { is_done: true, ..test_task("x marks the spot") },
+        ];
+
+        let data = round_trip(written.clone());
+
+        assert!(data.archive.is_empty());
+        assert_eq!(data.tasks.len(), written.len());
+        for (read, written) in data.tasks.iter().zip(&written) {
+            assert_same(read, written);
+        }
+    }
+
+    #[test]
+    fn open_tasks_keep_titles_that_look_like_dates_and_priorities() {
+        let written = vec![
+            test_task("2024-01-01 review"),
+            Task { priority: Some('C'), ..test_task("(A) call back") },
+            test_task("x marks the spot"),
+            Task { due: NaiveDate::from_ymd_opt(2024, 4, 1), ..test_task("") },
+        ];
+
+        let data = round_trip(written.clone());
+
+        assert_eq!(data.tasks.len(), written.len());
+        for (read, written) in data.tasks.iter().zip(&written) {
+            assert_same(read, written);
+        }
+    }
+
+    #[test]
+    fn archived_tasks_go_back_to_their_batch() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 2, 18, 0, 0).unwrap();
+        let written = Task { is_done: true, ..test_task("2024-01-01 (A) pri:B report") };
+        let archive = vec![ArchiveItem { date, tasks: vec![written.clone()] }];
+
+        let data = read(&write(&Data { tasks: vec![], archive })).unwrap();
+
+        assert!(data.tasks.is_empty());
+        assert_eq!(data.archive.len(), 1);
+        assert_eq!(data.archive[0].date, date);
+        assert_same(&data.archive[0].tasks[0], &written);
+    }
+
+    #[test]
+    fn done_tasks_of_other_tools_are_archived() {
+        let data = read("x 2024-03-02 2024-03-01 Pay the rent").unwrap();
+
+        assert!(data.tasks.is_empty());
+        assert_eq!(data.archive[0].tasks[0].title, "Pay the rent");
+    }
+}
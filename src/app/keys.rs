@@ -0,0 +1,210 @@
+// ----------------------------------------------------------------------------
+// KEY BINDINGS SUB-MODULE
+// This submodule defines the key bindings of every app state. The event loop
+// dispatches on these tables and the help popup and the instruction bar are
+// rendered from them, so they always agree on what each key does.
+// ----------------------------------------------------------------------------
+
+use crate::app::AppState;
+
+use crossterm::event::KeyCode;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum Action {
+    // Application
+    Quit,
+    Save,
+    ToggleHelp,
+    ShowTasks,
+    ShowArchive,
+    ShowSettings,
+
+    // Task list
+    NextTask,
+    PrevTask,
+    MoveTaskUp,
+    MoveTaskDown,
+    AddTask,
+    EditTask,
+    DeleteTask,
+    ToggleDone,
+    ActivateTask,
+    ArchiveDone,
+
+    // Archive
+    NewerArchive,
+    OlderArchive,
+    DearchiveTask,
+
+    // Settings
+    NextSetting,
+    PrevSetting,
+    IncSetting,
+    DecSetting,
+    TypeValue,
+    SaveTheme,
+
+    // Settings text input
+    ApplyInput,
+    CancelInput,
+    DeleteInputChar,
+
+    // Editor
+    StopEditing,
+    SwitchField,
+    DeleteChar,
+    NewLine,
+    CursorLeft,
+    CursorRight,
+    CursorUp,
+    CursorDown,
+
+    // External changes popup
+    ReloadExternal,
+    KeepMine,
+    UseMine,
+    UseTheirs,
+
+    // Help popup
+    ScrollHelpDown,
+    ScrollHelpUp,
+}
+
+impl Action {
+    // Actions that change tasks or settings, disabled in read-only mode
+    pub fn is_mutating(&self) -> bool {
+        matches!(self,
+            Action::Save | Action::MoveTaskUp | Action::MoveTaskDown | Action::AddTask | Action::EditTask |
+            Action::DeleteTask | Action::ToggleDone | Action::ActivateTask | Action::ArchiveDone |
+            Action::DearchiveTask | Action::IncSetting | Action::DecSetting | Action::TypeValue |
+            Action::SaveTheme | Action::KeepMine | Action::UseMine | Action::UseTheirs
+        )
+    }
+}
+
+pub struct KeyBinding {
+    pub keys: &'static [KeyCode],
+    pub action: Action,
+    pub category: &'static str,
+    pub description: &'static str,
+    // Short description for the instruction bar, bindings without one aren't shown there
+    pub hint: &'static str,
+}
+
+const DISPLAY_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Char('j'), KeyCode::Down], action: Action::NextTask,     category: "Navigation", description: "Select next task", hint: "Go down" },
+    KeyBinding { keys: &[KeyCode::Char('k'), KeyCode::Up],   action: Action::PrevTask,     category: "Navigation", description: "Select previous task", hint: "Go up" },
+    KeyBinding { keys: &[KeyCode::Char('i')],                action: Action::MoveTaskUp,   category: "Navigation", description: "Move task up the list", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('u')],                action: Action::MoveTaskDown, category: "Navigation", description: "Move task down the list", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('a')],                action: Action::AddTask,      category: "Tasks",      description: "Add task", hint: "Add task" },
+    KeyBinding { keys: &[KeyCode::Char('e')],                action: Action::EditTask,     category: "Tasks",      description: "Edit task", hint: "Edit task" },
+    KeyBinding { keys: &[KeyCode::Char('d')],                action: Action::DeleteTask,   category: "Tasks",      description: "Delete task", hint: "Delete task" },
+    KeyBinding { keys: &[KeyCode::Char(' ')],                action: Action::ToggleDone,   category: "Tasks",      description: "Mark task as done / not done", hint: "Mark task as done" },
+    KeyBinding { keys: &[KeyCode::Enter],                    action: Action::ActivateTask, category: "Tasks",      description: "Start / stop task timer, confirm popup", hint: "Activate task" },
+    KeyBinding { keys: &[KeyCode::Char('c')],                action: Action::ArchiveDone,  category: "Tasks",      description: "Archive done tasks", hint: "Archive tasks" },
+    KeyBinding { keys: &[KeyCode::Char('s')],                action: Action::Save,         category: "App",        description: "Save tasks", hint: "Save tasks" },
+    KeyBinding { keys: &[KeyCode::Tab],                      action: Action::ShowArchive,  category: "App",        description: "Go to archive", hint: "Archive" },
+    KeyBinding { keys: &[KeyCode::BackTab],                  action: Action::ShowSettings, category: "App",        description: "Go to settings", hint: "Settings" },
+    KeyBinding { keys: &[KeyCode::Char('?'), KeyCode::F(1)], action: Action::ToggleHelp,   category: "App",        description: "Show this help", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('q'), KeyCode::Esc],  action: Action::Quit,         category: "App",        description: "Quit, cancel popup", hint: "Quit" },
+];
+
+const EDIT_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Left],      action: Action::CursorLeft,  category: "Cursor",  description: "Move cursor left", hint: "" },
+    KeyBinding { keys: &[KeyCode::Right],     action: Action::CursorRight, category: "Cursor",  description: "Move cursor right", hint: "" },
+    KeyBinding { keys: &[KeyCode::Up],        action: Action::CursorUp,    category: "Cursor",  description: "Move cursor up a line", hint: "" },
+    KeyBinding { keys: &[KeyCode::Down],      action: Action::CursorDown,  category: "Cursor",  description: "Move cursor down a line", hint: "" },
+    KeyBinding { keys: &[KeyCode::Backspace], action: Action::DeleteChar,  category: "Editing", description: "Delete character", hint: "" },
+    KeyBinding { keys: &[KeyCode::Enter],     action: Action::NewLine,     category: "Editing", description: "Insert new line", hint: "New line" },
+    KeyBinding { keys: &[KeyCode::Tab],       action: Action::SwitchField, category: "Editing", description: "Switch between title and description", hint: "Title / description" },
+    KeyBinding { keys: &[KeyCode::F(1)],      action: Action::ToggleHelp,  category: "App",     description: "Show this help", hint: "" },
+    KeyBinding { keys: &[KeyCode::Esc],       action: Action::StopEditing, category: "App",     description: "Finish editing", hint: "Finish editing" },
+];
+
+const ARCHIVED_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Char('j'), KeyCode::Down],  action: Action::NextTask,      category: "Navigation", description: "Select next task", hint: "Go down" },
+    KeyBinding { keys: &[KeyCode::Char('k'), KeyCode::Up],    action: Action::PrevTask,      category: "Navigation", description: "Select previous task", hint: "Go up" },
+    KeyBinding { keys: &[KeyCode::Char('h'), KeyCode::Left],  action: Action::NewerArchive,  category: "Navigation", description: "Newer archive", hint: "Newer archive" },
+    KeyBinding { keys: &[KeyCode::Char('l'), KeyCode::Right], action: Action::OlderArchive,  category: "Navigation", description: "Older archive", hint: "Older archive" },
+    KeyBinding { keys: &[KeyCode::Char(' ')],                 action: Action::DearchiveTask, category: "Tasks",      description: "Dearchive task", hint: "Dearchive task" },
+    KeyBinding { keys: &[KeyCode::Tab],                       action: Action::ShowSettings,  category: "App",        description: "Go to settings", hint: "Settings" },
+    KeyBinding { keys: &[KeyCode::BackTab],                   action: Action::ShowTasks,     category: "App",        description: "Go to tasks", hint: "Tasks" },
+    KeyBinding { keys: &[KeyCode::Char('?'), KeyCode::F(1)],  action: Action::ToggleHelp,    category: "App",        description: "Show this help", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('q'), KeyCode::Esc],   action: Action::Quit,          category: "App",        description: "Quit", hint: "Quit" },
+];
+
+const SETTINGS_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Down],                      action: Action::NextSetting, category: "Settings", description: "Select next setting", hint: "Next setting" },
+    KeyBinding { keys: &[KeyCode::Up],                        action: Action::PrevSetting, category: "Settings", description: "Select previous setting", hint: "Previous setting" },
+    KeyBinding { keys: &[KeyCode::Right],                     action: Action::IncSetting,  category: "Settings", description: "Next value", hint: "Next value" },
+    KeyBinding { keys: &[KeyCode::Left],                      action: Action::DecSetting,  category: "Settings", description: "Previous value", hint: "Previous value" },
+    KeyBinding { keys: &[KeyCode::Enter],                     action: Action::TypeValue,  category: "Settings", description: "Type colour as name, #rrggbb or 0-255", hint: "Type colour" },
+    KeyBinding { keys: &[KeyCode::Char('w')],                 action: Action::SaveTheme,   category: "Settings", description: "Save colours as a named theme", hint: "Save theme" },
+    KeyBinding { keys: &[KeyCode::Tab],                       action: Action::ShowTasks,   category: "App",      description: "Go to tasks", hint: "Tasks" },
+    KeyBinding { keys: &[KeyCode::BackTab],                   action: Action::ShowArchive, category: "App",      description: "Go to archive", hint: "Archive" },
+    KeyBinding { keys: &[KeyCode::Char('h'), KeyCode::Char('l')], action: Action::ShowTasks, category: "App",    description: "Back to tasks", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('?'), KeyCode::F(1)],  action: Action::ToggleHelp,  category: "App",      description: "Show this help", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('q'), KeyCode::Esc],   action: Action::Quit,        category: "App",      description: "Quit", hint: "Quit" },
+];
+
+const SETTING_INPUT_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Enter],     action: Action::ApplyInput,      category: "Input", description: "Apply colour or save theme", hint: "" },
+    KeyBinding { keys: &[KeyCode::Backspace], action: Action::DeleteInputChar, category: "Input", description: "Delete character", hint: "" },
+    KeyBinding { keys: &[KeyCode::Esc],       action: Action::CancelInput,     category: "Input", description: "Cancel", hint: "" },
+];
+
+const CONFLICT_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Char('r')], action: Action::ReloadExternal, category: "Changed on disk", description: "Reload the file, dropping unsaved changes", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('k')], action: Action::KeepMine,       category: "Changed on disk", description: "Keep unsaved changes, overwriting the file", hint: "" },
+];
+
+const MERGE_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Char('m')], action: Action::UseMine,        category: "Changed on disk", description: "Use the value from here", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('t')], action: Action::UseTheirs,      category: "Changed on disk", description: "Use the value from the file", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('r')], action: Action::ReloadExternal, category: "Changed on disk", description: "Reload the file, dropping unsaved changes", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('k')], action: Action::KeepMine,       category: "Changed on disk", description: "Keep unsaved changes, overwriting the file", hint: "" },
+];
+
+const HELP_BINDINGS: &[KeyBinding] = &[
+    KeyBinding { keys: &[KeyCode::Char('j'), KeyCode::Down], action: Action::ScrollHelpDown, category: "Help", description: "Scroll down", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('k'), KeyCode::Up],   action: Action::ScrollHelpUp,   category: "Help", description: "Scroll up", hint: "" },
+    KeyBinding { keys: &[KeyCode::Char('?'), KeyCode::F(1), KeyCode::Char('q'), KeyCode::Esc], action: Action::ToggleHelp, category: "Help", description: "Close help", hint: "" },
+];
+
+// Returns the bindings active in the given state
+pub fn bindings(state: AppState) -> &'static [KeyBinding] {
+    match state {
+        AppState::Display  => DISPLAY_BINDINGS,
+        AppState::EditTask => EDIT_BINDINGS,
+        AppState::Archived => ARCHIVED_BINDINGS,
+        AppState::Settings => SETTINGS_BINDINGS,
+    }
+}
+
+// Returns the bindings active while the help popup is shown
+pub fn help_bindings() -> &'static [KeyBinding] {
+    HELP_BINDINGS
+}
+
+// Returns the bindings active while a value is typed in the settings
+pub fn setting_input_bindings() -> &'static [KeyBinding] {
+    SETTING_INPUT_BINDINGS
+}
+
+// Returns the bindings active while asking about files changed on disk
+pub fn conflict_bindings() -> &'static [KeyBinding] {
+    CONFLICT_BINDINGS
+}
+
+// Returns the bindings active while merging tasks changed both here and on disk
+pub fn merge_bindings() -> &'static [KeyBinding] {
+    MERGE_BINDINGS
+}
+
+// Looks up the action bound to a key in a binding table
+pub fn find_action(table: &[KeyBinding], code: KeyCode) -> Option<Action> {
+    table
+        .iter()
+        .find(|binding| binding.keys.contains(&code))
+        .map(|binding| binding.action)
+}
@@ -0,0 +1,59 @@
+// ----------------------------------------------------------------------------
+// LOCK SUB-MODULE
+// This submodule keeps two instances of the app from working on the same data
+// folder. The lock file is locked through the operating system on startup and
+// holds the process id, for messages. The system releases the lock when the
+// lock is dropped or the process ends in any way, so a crash never leaves a
+// stale lock behind. The file itself stays, removing it would let another
+// instance lock a file no one else can find.
+// ----------------------------------------------------------------------------
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+// ---- CONSTANTS ----
+const LOCK_FILE: &str = "todo_rust.lock";
+
+pub struct DataLock {
+    file: File,
+}
+
+impl DataLock {
+    // Takes the lock of the data folder, returns None if another running
+    // instance holds it
+    pub fn acquire(data_dir: &Path) -> io::Result<Option<DataLock>> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(lock_path(data_dir))?;
+
+        match file.try_lock() {
+            Ok(()) => {},
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+
+        file.set_len(0)?;
+        write!(file, "{}", process::id())?;
+
+        Ok(Some(DataLock { file }))
+    }
+}
+
+impl Drop for DataLock {
+    fn drop(&mut self) {
+        // The process id goes before the lock is released
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+pub fn lock_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(LOCK_FILE)
+}
+
+// Process id of the instance holding the lock of the data folder
+pub fn holder(data_dir: &Path) -> Option<u32> {
+    fs::read_to_string(lock_path(data_dir))
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+}
@@ -0,0 +1,661 @@
+// ----------------------------------------------------------------------------
+// MERGE SUB-MODULE
+// This submodule combines two copies of the tasks that were changed apart
+// from a common one, task by task using their ids. A field changed on one side
+// only takes that change. Tracked time is added up from both sides and the
+// sessions joined, and where a task is (the list or an archive batch) is
+// merged like any other field. A field changed on both sides to different
+// values, or a task removed on one side and changed on the other, is a
+// conflict. Conflicts keep our side until they are resolved otherwise.
+// ----------------------------------------------------------------------------
+
+use super::ArchiveItem;
+use super::task::{Session, Task};
+use super::schema::{self, DataFile};
+
+use crate::error::{Error, Result};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+
+use clap::ValueEnum;
+
+use serde_json::Value;
+
+use uuid::Uuid;
+
+// Where a task is kept
+#[derive(Clone, Copy, PartialEq)]
+enum Place {
+    List,
+    Archive(DateTime<Utc>),
+}
+
+// What was changed on both sides
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Title,
+    Description,
+    Done,
+    Priority,
+    Due,
+    Place,
+    // Removed on one side and changed on the other
+    Removed,
+}
+
+impl Field {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Title       => "title",
+            Field::Description => "description",
+            Field::Done        => "done",
+            Field::Priority    => "priority",
+            Field::Due         => "due date",
+            Field::Place       => "list",
+            Field::Removed     => "kept or removed",
+        }
+    }
+}
+
+pub struct MergeConflict {
+    pub id: Uuid,
+    pub title: String,
+    pub field: Field,
+    // The values of each side as they are shown
+    pub ours: String,
+    pub theirs: String,
+    pub use_theirs: bool,
+    // Their task to take the value from, or the one to bring back when
+    // their change wins over our removal
+    other: Option<(Task, Place)>,
+}
+
+pub struct Merge {
+    // Every task by id in order, None for removed ones
+    results: Vec<(Uuid, Option<(Task, Place)>)>,
+    conflicts: Vec<MergeConflict>,
+    // The first conflict without a choice
+    current: usize,
+}
+
+// Which side fields changed on both take
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Prefer {
+    Ours,
+    Theirs,
+}
+
+// Every task of a copy by id, and the order they come in
+struct Side {
+    tasks: HashMap<Uuid, (Task, Place)>,
+    order: Vec<Uuid>,
+}
+
+impl Side {
+    fn new(tasks: &[Task], archive: &[ArchiveItem]) -> Side {
+        let mut copy = Side { tasks: HashMap::new(), order: vec![] };
+
+        let all = tasks
+            .iter()
+            .map(|t| (t, Place::List))
+            .chain(archive.iter().flat_map(|a| a.tasks.iter().map(move |t| (t, Place::Archive(a.date)))));
+
+        for (task, place) in all {
+            if copy.tasks.insert(task.id, (task.clone(), place)).is_none() {
+                copy.order.push(task.id);
+            }
+        }
+
+        copy
+    }
+}
+
+// Merges our and their copies of the list and the archive, which both come from base
+pub(super) fn merge(
+    base: (&[Task], &[ArchiveItem]),
+    ours: (&[Task], &[ArchiveItem]),
+    theirs: (&[Task], &[ArchiveItem]),
+) -> Merge {
+    let base = Side::new(base.0, base.1);
+    let ours = Side::new(ours.0, ours.1);
+    let theirs = Side::new(theirs.0, theirs.1);
+
+    // Our order first, then the tasks only they have
+    let mut order = ours.order.clone();
+    order.extend(theirs.order.iter().filter(|id| !ours.tasks.contains_key(id)));
+
+    let mut results = vec![];
+    let mut conflicts = vec![];
+
+    for id in order {
+        let merged = match (base.tasks.get(&id), ours.tasks.get(&id), theirs.tasks.get(&id)) {
+            (base, Some(ours), Some(theirs)) => Some(merge_task(base, ours, theirs, &mut conflicts)),
+            (Some(base), Some(kept), None) | (Some(base), None, Some(kept)) => {
+                if same_task(&base.0, &kept.0) && base.1 == kept.1 {
+                    None
+                } else {
+                    // Removed on one side after being changed on the other
+                    let removed_by_us = !ours.tasks.contains_key(&id);
+                    conflicts.push(MergeConflict {
+                        id,
+                        title: first_line(&kept.0.title),
+                        field: Field::Removed,
+                        ours: String::from(if removed_by_us { "removed" } else { "changed" }),
+                        theirs: String::from(if removed_by_us { "changed" } else { "removed" }),
+                        use_theirs: false,
+                        other: if removed_by_us { Some(kept.clone()) } else { None },
+                    });
+
+                    if removed_by_us { None } else { Some(kept.clone()) }
+                }
+            },
+            (None, Some(added), None) | (None, None, Some(added)) => Some(added.clone()),
+            _ => None,
+        };
+
+        results.push((id, merged));
+    }
+
+    Merge { results, conflicts, current: 0 }
+}
+
+impl Merge {
+    pub fn conflicts(&self) -> &[MergeConflict] {
+        &self.conflicts
+    }
+
+    // The next conflict to choose a side for, and its number
+    pub fn current(&self) -> Option<(usize, &MergeConflict)> {
+        self.conflicts.get(self.current).map(|c| (self.current, c))
+    }
+
+    // Chooses a side for the current conflict and moves to the next one
+    pub fn resolve(&mut self, use_theirs: bool) {
+        if let Some(conflict) = self.conflicts.get_mut(self.current) {
+            conflict.use_theirs = use_theirs;
+            self.current += 1;
+        }
+    }
+
+    // The merged list and archive, with the side chosen for every conflict
+    pub(super) fn finish(self) -> (Vec<Task>, Vec<ArchiveItem>) {
+        let mut results = self.results;
+
+        for conflict in self.conflicts.iter().filter(|c| c.use_theirs) {
+            let Some((_, result)) = results.iter_mut().find(|(id, _)| *id == conflict.id) else {
+                continue;
+            };
+
+            if conflict.field == Field::Removed {
+                // Whichever was kept goes and whichever was removed comes back
+                *result = match result {
+                    Some(_) => None,
+                    None => conflict.other.clone(),
+                };
+                continue;
+            }
+
+            let (Some((task, place)), Some((other, other_place))) = (result.as_mut(), conflict.other.as_ref()) else {
+                continue;
+            };
+            match conflict.field {
+                Field::Title       => task.title = other.title.clone(),
+                Field::Description => task.description = other.description.clone(),
+                Field::Done        => task.is_done = other.is_done,
+                Field::Priority    => task.priority = other.priority,
+                Field::Due         => task.due = other.due,
+                Field::Place       => *place = *other_place,
+                Field::Removed     => {},
+            }
+        }
+
+        let mut tasks = vec![];
+        let mut archive: Vec<ArchiveItem> = vec![];
+
+        for (task, place) in results.into_iter().filter_map(|(_, result)| result) {
+            match place {
+                Place::List => tasks.push(task),
+                Place::Archive(date) => match archive.iter_mut().find(|a| a.date == date) {
+                    Some(item) => item.tasks.push(task),
+                    None => archive.push(ArchiveItem { date, tasks: vec![task] }),
+                },
+            }
+        }
+
+        archive.sort_by_key(|a| a.date);
+
+        (tasks, archive)
+    }
+}
+
+// ---- FILES ----
+// Merges copies of tasks.json or archive.json, or of whole data folders so that
+// tasks moved between the list and the archive are followed. The result goes to
+// ours unless another output is given, "-" being stdout, like git merge drivers
+// do. Returns the conflicts as they are shown, with the side that was taken.
+pub fn merge_paths(base: &Path, ours: &Path, theirs: &Path, output: Option<&Path>, prefer: Prefer) -> Result<Vec<String>> {
+    let output = output.unwrap_or(ours);
+
+    if ours.is_dir() {
+        let (base, ours, theirs) = (read_folder(base)?, read_folder(ours)?, read_folder(theirs)?);
+        let mut merge = merge((&base.0, &base.1), (&ours.0, &ours.1), (&theirs.0, &theirs.1));
+        let notes = resolve_all(&mut merge, prefer);
+        let (tasks, archive) = merge.finish();
+
+        fs::create_dir_all(output)?;
+        schema::write_file(&output.join("tasks.json"), &schema::to_vec(&tasks)?)?;
+        schema::write_file(&output.join("archive.json"), &schema::to_vec(&archive)?)?;
+
+        return Ok(notes);
+    }
+
+    let contents = [fs::read_to_string(base)?, fs::read_to_string(ours)?, fs::read_to_string(theirs)?];
+    let archive_file = contents.iter().any(|c| is_archive(c));
+
+    let mut copies = vec![];
+    for content in &contents {
+        copies.push(if archive_file {
+            (vec![], parse_file(content, DataFile::Archive)?)
+        } else {
+            (parse_file(content, DataFile::Tasks)?, vec![])
+        });
+    }
+
+    let mut merge = merge(
+        (&copies[0].0, &copies[0].1),
+        (&copies[1].0, &copies[1].1),
+        (&copies[2].0, &copies[2].1),
+    );
+    let notes = resolve_all(&mut merge, prefer);
+    let (tasks, archive) = merge.finish();
+
+    let content = if archive_file { schema::to_vec(&archive)? } else { schema::to_vec(&tasks)? };
+
+    if output == Path::new("-") {
+        io::stdout().write_all(&content)?;
+    } else {
+        schema::write_file(output, &content)?;
+    }
+
+    Ok(notes)
+}
+
+fn resolve_all(merge: &mut Merge, prefer: Prefer) -> Vec<String> {
+    while merge.current().is_some() {
+        merge.resolve(matches!(prefer, Prefer::Theirs));
+    }
+
+    merge.conflicts
+        .iter()
+        .map(|c| format!(
+            "'{}': {} is '{}' in ours and '{}' in theirs, took {}",
+            c.title, c.field.name(), c.ours, c.theirs, if c.use_theirs { "theirs" } else { "ours" },
+        ))
+        .collect()
+}
+
+fn read_folder(dir: &Path) -> Result<(Vec<Task>, Vec<ArchiveItem>)> {
+    let read = |name: &str| match fs::read_to_string(dir.join(name)) {
+        Ok(content) => Ok(content),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(Error::from(err)),
+    };
+
+    Ok((parse_file(&read("tasks.json")?, DataFile::Tasks)?, parse_file(&read("archive.json")?, DataFile::Archive)?))
+}
+
+// Empty files are empty lists, as git gives when there is no common copy
+fn parse_file<T: serde::de::DeserializeOwned + Default>(content: &str, kind: DataFile) -> Result<T> {
+    if content.trim().is_empty() {
+        return Ok(T::default());
+    }
+
+    Ok(schema::parse(content, kind)?.0)
+}
+
+// Archive files hold batches with their tasks in them, tasks.json the tasks
+fn is_archive(content: &str) -> bool {
+    let Ok(value) = serde_json::from_str::<Value>(content) else {
+        return false;
+    };
+    let items = value.get("data").unwrap_or(&value);
+
+    items.as_array().and_then(|a| a.first()).is_some_and(|item| item.get("tasks").is_some())
+}
+
+fn merge_task(
+    base: Option<&(Task, Place)>,
+    ours: &(Task, Place),
+    theirs: &(Task, Place),
+    conflicts: &mut Vec<MergeConflict>,
+) -> (Task, Place) {
+    let (base_task, base_place) = match base {
+        Some((task, place)) => (Some(task), Some(place)),
+        None => (None, None),
+    };
+    let (our_task, our_place) = ours;
+    let (their_task, their_place) = theirs;
+
+    let mut conflict = |field: Field, ours: String, theirs: String| {
+        conflicts.push(MergeConflict {
+            id: our_task.id,
+            title: first_line(&our_task.title),
+            field,
+            ours,
+            theirs,
+            use_theirs: false,
+            other: Some((their_task.clone(), *their_place)),
+        });
+    };
+
+    let title = pick(base_task.map(|t| &t.title), &our_task.title, &their_task.title);
+    if title.1 {
+        conflict(Field::Title, first_line(&our_task.title), first_line(&their_task.title));
+    }
+    let description = pick(base_task.map(|t| &t.description), &our_task.description, &their_task.description);
+    if description.1 {
+        conflict(Field::Description, first_line(&our_task.description), first_line(&their_task.description));
+    }
+    let is_done = pick(base_task.map(|t| &t.is_done), &our_task.is_done, &their_task.is_done);
+    if is_done.1 {
+        conflict(Field::Done, done_to_string(our_task.is_done), done_to_string(their_task.is_done));
+    }
+    let priority = pick(base_task.map(|t| &t.priority), &our_task.priority, &their_task.priority);
+    if priority.1 {
+        conflict(Field::Priority, priority_to_string(our_task.priority), priority_to_string(their_task.priority));
+    }
+    let due = pick(base_task.map(|t| &t.due), &our_task.due, &their_task.due);
+    if due.1 {
+        conflict(Field::Due, due_to_string(our_task.due), due_to_string(their_task.due));
+    }
+    let place = pick(base_place, our_place, their_place);
+    if place.1 {
+        conflict(Field::Place, place_to_string(*our_place), place_to_string(*their_place));
+    }
+
+    let task = Task {
+        id: our_task.id,
+        title: title.0,
+        description: description.0,
+        is_done: is_done.0,
+        priority: priority.0,
+        due: due.0,
+        // Set once, the two sides only differ for tasks added on both
+        created_on: our_task.created_on.min(their_task.created_on),
+        elapsed_time: add_time(base_task.map(|t| t.elapsed_time), our_task.elapsed_time, their_task.elapsed_time),
+        daily_time: add_days(base_task.map(|t| &t.daily_time), &our_task.daily_time, &their_task.daily_time),
+        sessions: join_sessions(&our_task.sessions, &their_task.sessions),
+        is_active: our_task.is_active,
+        is_selected: our_task.is_selected,
+    };
+
+    (task, place.0)
+}
+
+// The side that changed the value, ours when both did. Also returns whether
+// both changed it to different values.
+fn pick<T: PartialEq + Clone>(base: Option<&T>, ours: &T, theirs: &T) -> (T, bool) {
+    if base == Some(ours) {
+        (theirs.clone(), false)
+    } else {
+        (ours.clone(), ours != theirs && base != Some(theirs))
+    }
+}
+
+// Time tracked since base on either side is added to it. Tasks added on both
+// sides, e.g. by importing the same file, share what they have in common.
+fn add_time(base: Option<Duration>, ours: Duration, theirs: Duration) -> Duration {
+    let base = base.unwrap_or_else(|| ours.min(theirs));
+    base + ours.saturating_sub(base) + theirs.saturating_sub(base)
+}
+
+fn add_days(base: Option<&BTreeMap<NaiveDate, Duration>>, ours: &BTreeMap<NaiveDate, Duration>, theirs: &BTreeMap<NaiveDate, Duration>) -> BTreeMap<NaiveDate, Duration> {
+    let mut days = ours.clone();
+    days.extend(theirs.iter().map(|(day, time)| (*day, *time)));
+
+    for (day, time) in days.iter_mut() {
+        // Days the base task didn't have were tracked apart on each side
+        let base_time = base.map(|b| b.get(day).copied().unwrap_or_default());
+        *time = add_time(base_time, ours.get(day).copied().unwrap_or_default(), theirs.get(day).copied().unwrap_or_default());
+    }
+
+    days
+}
+
+// Both sides keep the sessions of base, the ones that start together are the
+// same session which may have run longer on one side
+fn join_sessions(ours: &[Session], theirs: &[Session]) -> Vec<Session> {
+    let mut sessions: Vec<Session> = ours.to_vec();
+
+    for session in theirs {
+        match sessions.iter_mut().find(|s| s.start == session.start) {
+            Some(same) => same.end = same.end.max(session.end),
+            None => sessions.push(*session),
+        }
+    }
+
+    sessions.sort_by_key(|s| s.start);
+    sessions
+}
+
+// Whether a task is as it was, selection and timers aside
+fn same_task(a: &Task, b: &Task) -> bool {
+    a.title == b.title
+        && a.description == b.description
+        && a.is_done == b.is_done
+        && a.priority == b.priority
+        && a.due == b.due
+        && a.elapsed_time == b.elapsed_time
+}
+
+// ---- SHOWN VALUES ----
+fn first_line(text: &str) -> String {
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or("").to_string();
+    if lines.next().is_some() { format!("{} ...", first) } else { first }
+}
+
+fn done_to_string(is_done: bool) -> String {
+    String::from(if is_done { "done" } else { "not done" })
+}
+
+fn priority_to_string(priority: Option<char>) -> String {
+    priority.map(|p| format!("({})", p)).unwrap_or_else(|| String::from("none"))
+}
+
+fn due_to_string(due: Option<NaiveDate>) -> String {
+    due.map(|d| d.format("%Y/%m/%d").to_string()).unwrap_or_else(|| String::from("none"))
+}
+
+fn place_to_string(place: Place) -> String {
+    match place {
+        Place::List => String::from("task list"),
+        Place::Archive(date) => format!("archive of {}", date.with_timezone(&Local).format("%Y/%m/%d")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::task::test_task;
+
+    use chrono::TimeZone;
+
+    fn session(start_min: u32, end_min: u32) -> Session {
+        Session {
+            start: Utc.with_ymd_and_hms(2024, 3, 1, 10, start_min, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2024, 3, 1, 10, end_min, 0).unwrap(),
+        }
+    }
+
+    // Merges three copies of the task list, the archives left empty
+    fn merge_lists(base: &[Task], ours: &[Task], theirs: &[Task]) -> Merge {
+        merge((base, &[]), (ours, &[]), (theirs, &[]))
+    }
+
+    fn titles(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn independent_edits_are_both_kept() {
+        let base = test_task("Write report");
+        let ours = Task { title: String::from("Write the report"), ..base.clone() };
+        let theirs = Task { due: NaiveDate::from_ymd_opt(2024, 4, 1), priority: Some('B'), ..base.clone() };
+
+        let merge = merge_lists(&[base], &[ours], &[theirs]);
+        assert!(merge.conflicts().is_empty());
+
+        let (tasks, archive) = merge.finish();
+        assert!(archive.is_empty());
+        assert_eq!(titles(&tasks), ["Write the report"]);
+        assert_eq!(tasks[0].due, NaiveDate::from_ymd_opt(2024, 4, 1));
+        assert_eq!(tasks[0].priority, Some('B'));
+    }
+
+    #[test]
+    fn fields_changed_on_both_sides_conflict() {
+        let base = [test_task("Write report")];
+        let ours = [Task { title: String::from("Ours"), ..base[0].clone() }];
+        let theirs = [Task { title: String::from("Theirs"), ..base[0].clone() }];
+
+        let merge = merge_lists(&base, &ours, &theirs);
+        assert_eq!(merge.conflicts().len(), 1);
+        assert!(merge.conflicts()[0].field == Field::Title);
+
+        for (prefer, title) in [(Prefer::Ours, "Ours"), (Prefer::Theirs, "Theirs")] {
+            let mut merge = merge_lists(&base, &ours, &theirs);
+            let notes = resolve_all(&mut merge, prefer);
+            assert_eq!(notes.len(), 1);
+
+            let (tasks, _) = merge.finish();
+            assert_eq!(titles(&tasks), [title]);
+        }
+    }
+
+    #[test]
+    fn the_same_change_on_both_sides_is_no_conflict() {
+        let base = [test_task("Write report")];
+        let changed = [Task { is_done: true, ..base[0].clone() }];
+
+        let merge = merge_lists(&base, &changed, &changed);
+        assert!(merge.conflicts().is_empty());
+        assert!(merge.finish().0[0].is_done);
+    }
+
+    #[test]
+    fn removing_a_task_changed_on_the_other_side_conflicts() {
+        let base = [test_task("Write report")];
+        let changed = [Task { title: String::from("Write the report"), ..base[0].clone() }];
+
+        // Removed by us
+        let mut merge = merge_lists(&base, &[], &changed);
+        assert_eq!(merge.conflicts().len(), 1);
+        assert!(merge.conflicts()[0].field == Field::Removed);
+        resolve_all(&mut merge, Prefer::Ours);
+        assert!(merge.finish().0.is_empty());
+
+        let mut merge = merge_lists(&base, &[], &changed);
+        resolve_all(&mut merge, Prefer::Theirs);
+        assert_eq!(titles(&merge.finish().0), ["Write the report"]);
+
+        // Removed by them
+        let mut merge = merge_lists(&base, &changed, &[]);
+        resolve_all(&mut merge, Prefer::Ours);
+        assert_eq!(titles(&merge.finish().0), ["Write the report"]);
+
+        let mut merge = merge_lists(&base, &changed, &[]);
+        resolve_all(&mut merge, Prefer::Theirs);
+        assert!(merge.finish().0.is_empty());
+    }
+
+    #[test]
+    fn removing_an_unchanged_task_is_no_conflict() {
+        let base = [test_task("Write report")];
+
+        let merge = merge_lists(&base, &base, &[]);
+        assert!(merge.conflicts().is_empty());
+        assert!(merge.finish().0.is_empty());
+    }
+
+    #[test]
+    fn time_tracked_on_both_sides_is_added_up() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let other_day = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        let with_time = |total: u64, days: &[(NaiveDate, u64)]| Task {
+            elapsed_time: Duration::from_secs(total),
+            daily_time: days.iter().map(|(d, secs)| (*d, Duration::from_secs(*secs))).collect(),
+            ..test_task("Write report")
+        };
+
+        let base = with_time(60, &[(day, 60)]);
+        let ours = Task { id: base.id, ..with_time(90, &[(day, 90)]) };
+        let theirs = Task { id: base.id, ..with_time(180, &[(day, 60), (other_day, 120)]) };
+
+        let merge = merge_lists(&[base], &[ours], &[theirs]);
+        assert!(merge.conflicts().is_empty());
+
+        let (tasks, _) = merge.finish();
+        assert_eq!(tasks[0].elapsed_time, Duration::from_secs(60 + 30 + 120));
+        assert_eq!(tasks[0].daily_time[&day], Duration::from_secs(90));
+        assert_eq!(tasks[0].daily_time[&other_day], Duration::from_secs(120));
+    }
+
+    #[test]
+    fn time_of_tasks_added_on_both_sides_is_not_counted_twice() {
+        let ours = Task { elapsed_time: Duration::from_secs(60), ..test_task("Imported") };
+        let theirs = Task { elapsed_time: Duration::from_secs(90), ..ours.clone() };
+
+        let (tasks, _) = merge_lists(&[], &[ours], &[theirs]).finish();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].elapsed_time, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn sessions_are_joined_by_their_start() {
+        let base = Task { sessions: vec![session(0, 10)], ..test_task("Write report") };
+        let ours = Task { sessions: vec![session(0, 10), session(20, 30)], ..base.clone() };
+        let theirs = Task { sessions: vec![session(0, 15), session(40, 50)], ..base.clone() };
+
+        let (tasks, _) = merge_lists(&[base], &[ours], &[theirs]).finish();
+        assert!(tasks[0].sessions == [session(0, 15), session(20, 30), session(40, 50)]);
+    }
+
+    #[test]
+    fn tasks_added_on_one_side_are_kept() {
+        let base = test_task("Write report");
+        let ours_new = test_task("Ours");
+        let theirs_new = test_task("Theirs");
+
+        let merge = merge_lists(
+            std::slice::from_ref(&base),
+            &[base.clone(), ours_new],
+            &[theirs_new, base.clone()],
+        );
+        assert!(merge.conflicts().is_empty());
+        assert_eq!(titles(&merge.finish().0), ["Write report", "Ours", "Theirs"]);
+    }
+
+    #[test]
+    fn tasks_moved_to_the_archive_follow() {
+        let date = Utc.with_ymd_and_hms(2024, 3, 2, 18, 0, 0).unwrap();
+        let base = [test_task("Write report")];
+        let archive = [ArchiveItem { date, tasks: vec![Task { is_done: true, ..base[0].clone() }] }];
+        let theirs = [Task { priority: Some('A'), ..base[0].clone() }];
+
+        let merge = merge((&base, &[]), (&[], &archive), (&theirs, &[]));
+        assert!(merge.conflicts().is_empty());
+
+        let (tasks, archive) = merge.finish();
+        assert!(tasks.is_empty());
+        assert_eq!(archive.len(), 1);
+        assert!(archive[0].tasks[0].is_done);
+        assert_eq!(archive[0].tasks[0].priority, Some('A'));
+    }
+}
@@ -1,10 +1,13 @@
-use crate::app::{App, Settings, AppState, EditSettingField, PopupType};
+use crate::app::{App, Settings, AppState, EditSettingField, PopupType, Conflict};
 use crate::app::utils::*;
+use crate::app::task::duration_to_string;
+use crate::app::formats;
+use crate::app::keys::{Action, KeyBinding, bindings, help_bindings, conflict_bindings, merge_bindings};
 
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::Modifier,
+    style::{Color, Modifier, Style},
     text::{Spans, Span},
     widgets::{
         Block, BorderType, Borders, Clear, Paragraph, Tabs, Wrap,
@@ -12,31 +15,51 @@ use tui::{
     Frame,
 };
 
+
+// ---- CONSTANTS ----
+// Width of the "[ ] " checkbox in front of each task
+pub const CHECKBOX_WIDTH: u16 = 3;
+// Lines of the edit popup holding the title and the start of the description
+pub const EDITOR_TITLE_LINE: u16 = 3;
+pub const EDITOR_DESC_LINE: u16 = 7;
+// Smallest terminal the app is drawn in
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 16;
+// Below this width the duration column is dropped and the panes are stacked
+const COMPACT_WIDTH: u16 = 80;
+// Most key bindings shown on a line of the instruction bar
+const INSTRUCTION_COLUMNS: usize = 4;
+const INSTRUCTION_SEPARATOR: &str = " | ";
+
 pub fn term_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let size = f.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        render_too_small(f, app);
+        return;
+    }
+
     let chunks = create_chunks(f);
     render_menu(f, &chunks[0], app);
 
-    let disp_instructions = "' ' - Mark task as done | 'a' - Add task         | 'e' - Edit task        | 'd' - Delete task      \n'j' - Go up             | 'k' - Go down          | Tab - Archive          | Shift+Tab - Settings  \n'c' - Archive tasks     | 's' - Save tasks       | enter - Activate task  | esc,'q' - Quit         ";
-    let arch_instructions = "'j' - Go up             | 'k' - Go down          | Tab - Settings         | Shift+Tab - Tasks      \n'h' - Newer archive     | 'l' - Older archive    | ' ' - Dearchive task   | esc,'q' - Quit        ";
-    let sett_instructions = "Up/Down - Select        | Left/Right - Modify    | Tab - Archive          | Shift+Tab - Tasks      ";
-
     match app.state {
-        AppState::Display  => {
-            render_tasks(f, &chunks[1], app);
-            render_instructions(f, &chunks[2], &app.settings, &disp_instructions);
-        },
-        AppState::EditTask => {
-            render_tasks(f, &chunks[1], app);
-            render_instructions(f, &chunks[2], &app.settings, &disp_instructions);
-        },
-        AppState::Archived => {
-            render_archived(f, &chunks[1], app);
-            render_instructions(f, &chunks[2], &app.settings, &arch_instructions);
-        },
-        AppState::Settings => {
-            render_settings(f, &chunks[1], app);
-            render_instructions(f, &chunks[2], &app.settings, &sett_instructions);
-        },
+        AppState::Display | AppState::EditTask => render_tasks(f, &chunks[1], app),
+        AppState::Archived => render_archived(f, &chunks[1], app),
+        AppState::Settings => render_settings(f, &chunks[1], app),
+    }
+
+    let help_key = bindings(app.state).iter().find(|b| b.action == Action::ToggleHelp).map(|b| key_to_string(b.keys[0]));
+    // The top border takes a line of the area
+    let inst_str = instructions(app, chunks[2].width, chunks[2].height.saturating_sub(1), help_key.as_deref());
+    render_instructions(f, &chunks[2], &app.settings, &inst_str, help_key);
+
+    render_status_bar(f, &chunks[3], app);
+
+    if app.show_help {
+        render_help(f, app);
+    }
+
+    if let Some(conflict) = app.conflict {
+        render_conflict(f, app, conflict);
     }
 }
 
@@ -53,11 +76,84 @@ fn create_chunks<B: Backend>(f: &mut Frame<B>) -> Vec<Rect> {
                 Constraint::Length(2),
                 Constraint::Min(2),
                 Constraint::Length(4),
+                Constraint::Length(1),
             ].as_ref(),
         ).split(size)
 }
 
 
+// Whether the terminal is too narrow for the full layout
+fn is_compact<B: Backend>(f: &Frame<B>) -> bool {
+    f.size().width < COMPACT_WIDTH
+}
+
+
+// Split the main area into the list pane and the details pane
+fn split_panes(rect: &Rect, side_by_side: bool) -> Vec<Rect> {
+    Layout::default()
+        .direction(if side_by_side { Direction::Horizontal } else { Direction::Vertical })
+        .constraints(
+            [
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ]
+        ).split(*rect)
+}
+
+
+// Split the list pane into scroll bar, task titles and durations
+fn split_list(rect: &Rect, compact: bool) -> Vec<Rect> {
+    if compact {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Min(0),
+                Constraint::Length(0),
+            ]
+            ).split(*rect)
+    } else {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ]
+            ).split(*rect)
+    }
+}
+
+
+// Placeholder shown when the terminal is below the minimum size
+fn render_too_small<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let size = f.size();
+
+    // Nothing from the last full draw is on screen anymore
+    app.menu_tabs.clear();
+    app.task_list_area = Rect::default();
+    app.editor_area = Rect::default();
+
+    let message = Paragraph::new(vec![
+        Spans::from(vec![Span::styled("Terminal too small", app.settings.title)]),
+        Spans::from(vec![Span::styled(format!("{}x{}, need {}x{}", size.width, size.height, MIN_WIDTH, MIN_HEIGHT), app.settings.default)]),
+    ])
+        .style(app.settings.default)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+
+    let area = Rect {
+        x: size.x,
+        y: size.y + size.height.saturating_sub(2) / 2,
+        width: size.width,
+        height: size.height.min(2),
+    };
+
+    f.render_widget(Block::default().style(app.settings.default), size);
+    f.render_widget(message, area);
+}
+
+
 // Pop up layout
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -87,8 +183,8 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
 
 // Render menu
-fn render_menu<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &App) {
-    let menu_titles = vec!["Active tasks", "Archived tasks", "Settings"];
+fn render_menu<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
+    let menu_titles = ["Active tasks", "Archived tasks", "Settings"];
     let menu = menu_titles
         .iter()
         .map(|t| {
@@ -111,13 +207,60 @@ fn render_menu<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &App) {
         .highlight_style(app.settings.title)
         .divider(Span::styled("||", app.settings.default));
 
+    // Capture where each tab lands so they can be clicked, each title is
+    // padded by one space on each side and followed by the divider
+    app.menu_tabs.clear();
+    let mut x = rect.x;
+    for title in menu_titles.iter() {
+        let width = title.chars().count() as u16 + 2;
+        app.menu_tabs.push(Rect { x, y: rect.y, width, height: 1 });
+        x += width + 2;
+    }
+
     f.render_widget(tabs, *rect);
 }
 
 
+// Instruction bar of the current state, made from the hints of its key
+// bindings in as many columns as fit
+fn instructions(app: &App, width: u16, height: u16, help_key: Option<&str>) -> String {
+    let mut cells: Vec<String> = bindings(app.state)
+        .iter()
+        .filter(|b| !b.hint.is_empty())
+        .filter(|b| !app.read_only || !b.action.is_mutating())
+        .map(|b| format!("{} - {}", key_to_string(b.keys[0]), b.hint))
+        .collect();
+
+    let cell_width = cells.iter().map(|c| c.chars().count()).max().unwrap_or(0);
+    let columns = ((width as usize + INSTRUCTION_SEPARATOR.len()) / (cell_width + INSTRUCTION_SEPARATOR.len())).clamp(1, INSTRUCTION_COLUMNS);
+
+    // Bindings that don't fit give their place to a pointer to the help
+    let rows = (height as usize).saturating_sub(app.read_only as usize).max(1);
+    if cells.len() > rows * columns {
+        cells.truncate(rows * columns - 1);
+        cells.push(match help_key {
+            Some(key) => format!("{} - more", key),
+            None => String::from("..."),
+        });
+    }
+
+    let mut lines = vec![];
+    if app.read_only {
+        lines.push(String::from("Tasks and settings can't be changed in read-only mode"));
+    }
+    for row in cells.chunks(columns) {
+        let row: Vec<String> = row.iter().map(|c| format!("{:<width$}", c, width = cell_width)).collect();
+        lines.push(row.join(INSTRUCTION_SEPARATOR));
+    }
+
+    lines.join("\n")
+}
+
+
 // Render instructions
-fn render_instructions<B: Backend>(f: &mut Frame<B>, rect: &Rect, settings: &Settings, inst_str: &str) {
+fn render_instructions<B: Backend>(f: &mut Frame<B>, rect: &Rect, settings: &Settings, inst_str: &str, help_key: Option<String>) {
     // Render instructions
+    let title = help_key.map(|key| format!(" {} - All key bindings ", key)).unwrap_or_default();
     let instructions = Paragraph::new(inst_str)
         .style(settings.border)
         .alignment(Alignment::Center)
@@ -126,61 +269,187 @@ fn render_instructions<B: Backend>(f: &mut Frame<B>, rect: &Rect, settings: &Set
                 .borders(Borders::TOP)
                 .style(settings.border)
                 .border_type(BorderType::Double)
+                .title(title)
         );
 
     f.render_widget(instructions, *rect);
 }
 
 
-// Render tasks screen
-fn render_tasks<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
-    let vsplit_layout = if app.settings.is_horizontal {
-        Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
-            ]
-        ).split(*rect)
+// Render help popup listing the key bindings of the current state
+fn render_help<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let area = centered_rect(60, 70, f.size());
+
+    let mut help_content = vec![];
+    append_bindings(&mut help_content, &app.settings, bindings(app.state), app.read_only);
+    if app.state == AppState::EditTask {
+        help_content.push(Spans::from(vec![Span::styled(format!("  {:<24}{}", "Any other key", "Type character"), app.settings.default)]));
+    }
+    append_bindings(&mut help_content, &app.settings, help_bindings(), false);
+
+    // Keep the scroll within the content, which is shorter once the popup grows
+    app.help_max_scroll = (help_content.len() as u16).saturating_sub(area.height.saturating_sub(2));
+    app.help_scroll = app.help_scroll.min(app.help_max_scroll);
+
+    let help_box = Paragraph::new(help_content)
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(app.settings.border)
+                .title(" Key bindings ")
+        )
+        .scroll((app.help_scroll, 0));
+
+    f.render_widget(Clear, area);
+    f.render_widget(help_box, area);
+}
+
+
+// Render popup asking which version to keep of a file changed on disk, or for
+// tasks which side to take of a field changed both here and in the file
+fn render_conflict<B: Backend>(f: &mut Frame<B>, app: &App, conflict: Conflict) {
+    let area = centered_rect(60, 40, f.size());
+
+    let mut content = vec![Spans::from(vec![Span::styled("", app.settings.default)])];
+    match (conflict, app.merge.as_ref().and_then(|m| m.current().map(|c| (c, m.conflicts().len())))) {
+        (Conflict::Data, Some(((index, current), count))) => {
+            content.push(Spans::from(vec![Span::styled(format!("The tasks were also changed by another program, clash {} of {}:", index + 1, count), app.settings.title)]));
+            content.push(Spans::from(vec![Span::styled("", app.settings.default)]));
+            content.push(Spans::from(vec![Span::styled(format!("  {:<13}{}", "Task:", current.title), app.settings.default)]));
+            content.push(Spans::from(vec![Span::styled(format!("  {:<13}{}", "Changed:", current.field.name()), app.settings.default)]));
+            content.push(Spans::from(vec![Span::styled(format!("  {:<13}{}", "Here:", current.ours), app.settings.default)]));
+            content.push(Spans::from(vec![Span::styled(format!("  {:<13}{}", "In the file:", current.theirs), app.settings.default)]));
+            append_bindings(&mut content, &app.settings, merge_bindings(), app.read_only);
+        },
+        _ => {
+            let files = match conflict {
+                Conflict::Data => "tasks.json or archive.json",
+                Conflict::Settings => "settings.json",
+            };
+            content.push(Spans::from(vec![Span::styled(format!("{} was changed by another program", files), app.settings.title)]));
+            content.push(Spans::from(vec![Span::styled("while there were unsaved changes here.", app.settings.title)]));
+            append_bindings(&mut content, &app.settings, conflict_bindings(), app.read_only);
+        },
+    }
+
+    let conflict_box = Paragraph::new(content)
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(app.settings.border)
+                .title(" Changed on disk ")
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, area);
+    f.render_widget(conflict_box, area);
+}
+
+
+// Render status bar: save state, current list and the latest message on the
+// left, the running task and the time tracked today on the right
+fn render_status_bar<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &App) {
+    let mut left = vec![];
+    if app.read_only {
+        left.push(Span::styled(" READ-ONLY ", app.settings.highlight.add_modifier(Modifier::BOLD)));
+    } else if app.is_dirty() {
+        left.push(Span::styled("● Unsaved", app.settings.title));
     } else {
-        Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
-            ]
-        ).split(*rect)
+        left.push(Span::styled("Saved", app.settings.default));
+    }
+
+    let list = match app.state {
+        AppState::Display | AppState::EditTask => String::from("Tasks"),
+        AppState::Archived => match app.archive.get(app.curr_archive) {
+            Some(item) => format!("Archive {}", item.date.format("%Y/%m/%d")),
+            None => String::from("Archive"),
+        },
+        AppState::Settings => String::from("Settings"),
     };
+    left.push(Span::styled(format!(" | {}", list), app.settings.default));
 
-    let hsplit_layout = Layout::default()
+    if let Some(toast) = &app.toast {
+        let style = if toast.is_error {
+            app.settings.default.fg(Color::Red)
+        } else {
+            app.settings.title
+        };
+        left.push(Span::styled(" | ", app.settings.default));
+        left.push(Span::styled(toast.message.as_str(), style));
+    }
+
+    let mut right = String::new();
+    if let Some(task) = app.tasks.iter().find(|t| t.is_active) {
+        right.push_str(&format!("▶ {} {} | ", task.title.lines().next().unwrap_or(""), task.get_time_str()));
+    }
+    right.push_str(&format!("Today {}", duration_to_string(app.tracked_today())));
+
+    let right_width = (right.chars().count() as u16).min(rect.width / 2);
+    let layout = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Length(4),
-            Constraint::Percentage(50),
-            Constraint::Percentage(50),
-        ]
-        ).split(vsplit_layout[0]);
+        .constraints([Constraint::Min(0), Constraint::Length(right_width)].as_ref())
+        .split(*rect);
+
+    f.render_widget(Paragraph::new(Spans::from(left)).style(app.settings.default), layout[0]);
+    f.render_widget(Paragraph::new(Span::styled(right, app.settings.active_normal)).alignment(Alignment::Right), layout[1]);
+}
+
+
+// Add the bindings of a table to the help content, grouped by category. In
+// read-only mode the bindings that would change something are left out.
+fn append_bindings(content: &mut Vec<Spans>, settings: &Settings, table: &[KeyBinding], read_only: bool) {
+    let table: Vec<&KeyBinding> = table.iter().filter(|b| !(read_only && b.action.is_mutating())).collect();
+
+    let mut categories: Vec<&str> = vec![];
+    for binding in &table {
+        if !categories.contains(&binding.category) {
+            categories.push(binding.category);
+        }
+    }
+
+    for category in categories {
+        content.push(Spans::from(vec![Span::styled("", settings.default)]));
+        content.push(Spans::from(vec![Span::styled(category, settings.title.add_modifier(Modifier::UNDERLINED))]));
+
+        for binding in table.iter().filter(|b| b.category == category) {
+            let keys: Vec<String> = binding.keys.iter().map(|k| key_to_string(*k)).collect();
+            content.push(Spans::from(vec![Span::styled(format!("  {:<24}{}", keys.join(", "), binding.description), settings.default)]));
+        }
+    }
+}
+
+
+// Render tasks screen
+fn render_tasks<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
+    let compact = is_compact(f);
+    let vsplit_layout = split_panes(rect, app.settings.is_horizontal && !compact);
+
+    let hsplit_layout = split_list(&vsplit_layout[0], compact);
 
     // Capture displaying variables
-    app.desc_width_char = vsplit_layout[1].width - 2;
-    app.task_block_height = hsplit_layout[0].height - 2;
-    let default_style = app.settings.default.clone();
-    let border_style = app.settings.border.clone();
-    let title_style = app.settings.title.clone();
+    app.desc_width_char = vsplit_layout[1].width.saturating_sub(2);
+    app.task_block_height = hsplit_layout[0].height.saturating_sub(2);
+    app.fit_first_task();
+    app.task_list_area = Rect {
+        x: hsplit_layout[1].x,
+        y: hsplit_layout[1].y + 1,
+        width: hsplit_layout[1].width + hsplit_layout[2].width,
+        height: app.task_block_height,
+    };
+    let default_style = app.settings.default;
+    let border_style = app.settings.border;
+    let title_style = app.settings.title;
 
     // Render scroll bar
     let mut line = 0;
     let mut scroll_bar = vec![];
-    let mut scroll_perc = 0.0;
-    let mut scroll_line = 0;
-    let mut scroll_size = 0;
     if app.tasks.len() > app.task_block_height as usize {
-        scroll_size = ((app.task_block_height as f32 / app.tasks.len() as f32) * (app.task_block_height) as f32).floor() as u16;
+        let scroll_size = ((app.task_block_height as f32 / app.tasks.len() as f32) * (app.task_block_height) as f32).floor() as u16;
 
-        scroll_perc = (app.first_task as f32) / ((app.tasks.len() as u16 - app.task_block_height) as f32);
-        scroll_line = (scroll_perc * ((app.task_block_height - scroll_size) as f32)) as u16;
+        let scroll_perc = (app.first_task as f32) / ((app.tasks.len() as u16 - app.task_block_height) as f32);
+        let scroll_line = (scroll_perc * ((app.task_block_height - scroll_size) as f32)) as u16;
 
         // Write out the scroll bar
         while line < app.task_block_height {
@@ -208,7 +477,13 @@ fn render_tasks<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
             } else {
                 disp_string.push_str("[ ] ");
             }
+            if let Some(priority) = task.priority {
+                disp_string.push_str(&format!("({}) ", priority));
+            }
             disp_string.push_str(&task.title);
+            if let Some(due) = task.due {
+                disp_string.push_str(&format!(" (due {})", due.format("%Y/%m/%d")));
+            }
 
             let mut style = app.settings.default;
             if task.is_selected {
@@ -267,7 +542,7 @@ fn render_tasks<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
         .alignment(Alignment::Left)
         .block(
             Block::default()
-            .borders(Borders::TOP | Borders::BOTTOM)
+            .borders(if compact { Borders::TOP | Borders::BOTTOM | Borders::RIGHT } else { Borders::TOP | Borders::BOTTOM })
             .style(border_style)
             .title(" To Do ")
         );
@@ -299,7 +574,9 @@ fn render_tasks<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
 
     f.render_widget(scroll_block, hsplit_layout[0]);
     f.render_widget(task_block, hsplit_layout[1]);
-    f.render_widget(task_dur_block, hsplit_layout[2]);
+    if !compact {
+        f.render_widget(task_dur_block, hsplit_layout[2]);
+    }
     f.render_widget(task_description, vsplit_layout[1]);
 
     // Show whatever popup is needed
@@ -378,45 +655,37 @@ fn render_tasks<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
 
         f.render_widget(Clear, area);
         f.render_widget(edit_box, area);
+
+        // Capture the popup contents area so the editor can be clicked
+        app.editor_area = Rect {
+            x: area.x + 1,
+            y: area.y + 1,
+            width: area.width.saturating_sub(2),
+            height: area.height.saturating_sub(2),
+        };
     }
 }
 
 
 // Render archived screen
 fn render_archived<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
-    let vsplit_layout = if app.settings.is_horizontal {
-        Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
-            ]
-        ).split(*rect)
-    } else {
-        Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
-            ]
-        ).split(*rect)
-    };
+    let compact = is_compact(f);
+    let vsplit_layout = split_panes(rect, app.settings.is_horizontal && !compact);
 
-    let hsplit_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Length(4),
-            Constraint::Percentage(50),
-            Constraint::Percentage(50),
-        ]
-        ).split(vsplit_layout[0]);
+    let hsplit_layout = split_list(&vsplit_layout[0], compact);
 
     // Capture displaying variables
-    app.desc_width_char = vsplit_layout[1].width - 2;
-    let default_style = app.settings.default.clone();
-    let border_style = app.settings.border.clone();
+    app.desc_width_char = vsplit_layout[1].width.saturating_sub(2);
+    app.task_block_height = hsplit_layout[0].height.saturating_sub(2);
+    app.fit_first_task();
+    app.task_list_area = Rect {
+        x: hsplit_layout[1].x,
+        y: hsplit_layout[1].y + 1,
+        width: hsplit_layout[1].width + hsplit_layout[2].width,
+        height: app.task_block_height,
+    };
+    let default_style = app.settings.default;
+    let border_style = app.settings.border;
 
     // Render archive items
     let mut archive_title = String::from("");
@@ -425,7 +694,7 @@ fn render_archived<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
     if let Some(archive_item) = app.get_curr_archive_item() {
         let converted_date = format!("{}", archive_item.date.format("%Y/%m/%d"));
         archive_title.push_str(&converted_date);
-        if app.archive.len() > 0 {
+        if !app.archive.is_empty() {
             if app.curr_archive > 0 {
                 archive_title.push(' ');
                 archive_title.push('-');
@@ -492,12 +761,12 @@ fn render_archived<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
 
     // Render scroll bar
     let mut scroll_bar = vec![];
-    if app.archive.len() > 0 {
+    if !app.archive.is_empty() {
         let mut line = 0;
         let scroll_perc;
         if app.archive[app.curr_archive].tasks.len() > app.task_block_height as usize {
             scroll_perc = (app.first_task as f32) / ((app.archive[app.curr_archive].tasks.len() as u16 - app.task_block_height) as f32);
-            let scroll_line = (scroll_perc * (app.task_block_height - 1) as f32) as u16;
+            let scroll_line = (scroll_perc * app.task_block_height.saturating_sub(1) as f32) as u16;
 
             while line < app.task_block_height {
                 if line == scroll_line {
@@ -527,7 +796,7 @@ fn render_archived<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
         .alignment(Alignment::Left)
         .block(
             Block::default()
-            .borders(Borders::TOP | Borders::BOTTOM)
+            .borders(if compact { Borders::TOP | Borders::BOTTOM | Borders::RIGHT } else { Borders::TOP | Borders::BOTTOM })
             .style(border_style)
             .title(archive_title)
         );
@@ -550,7 +819,7 @@ fn render_archived<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
 
     let mut task_title = String::from(" ");
     task_title.push_str(&app.get_sel_task_title().unwrap_or_else(|| { String::from("") }));
-    task_title.push_str(" ");
+    task_title.push(' ');
 
     let task_description = Paragraph::new(app.get_sel_task_info().unwrap_or_else(|| { vec![Spans::from(vec![Span::styled("", default_style)])] }))
         .alignment(Alignment::Left)
@@ -564,32 +833,17 @@ fn render_archived<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
 
     f.render_widget(scroll_block, hsplit_layout[0]);
     f.render_widget(archive_block, hsplit_layout[1]);
-    f.render_widget(archive_dur_block, hsplit_layout[2]);
+    if !compact {
+        f.render_widget(archive_dur_block, hsplit_layout[2]);
+    }
     f.render_widget(task_description, vsplit_layout[1]);
 }
 
 
 // Render settings
 fn render_settings<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
-    let vsplit_layout = if app.settings.is_horizontal {
-        Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
-            ]
-        ).split(*rect)
-    } else {
-        Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Percentage(50),
-                Constraint::Percentage(50),
-            ]
-        ).split(*rect)
-    };
+    let compact = is_compact(f);
+    let vsplit_layout = split_panes(rect, app.settings.is_horizontal && !compact);
 
     let hsplit_layout = Layout::default()
         .direction(Direction::Horizontal)
@@ -601,64 +855,84 @@ fn render_settings<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
         ).split(vsplit_layout[0]);
 
     // Capture displaying variables
-    let border_style = app.settings.border.clone();
+    let border_style = app.settings.border;
+
+    // Render settings, one heading per section followed by its fields
+    let sections = [
+        ("Layout", vec![
+            ("Split", EditSettingField::Split),
+        ]),
+        ("Saving", vec![
+            ("Autosave after changes", EditSettingField::AutosaveDelay),
+            ("Save while timing, every", EditSettingField::Checkpoint),
+            ("Calendar file", EditSettingField::CalendarFile),
+        ]),
+        ("Theme", vec![
+            ("Theme", EditSettingField::Theme),
+        ]),
+        ("Task colours", vec![
+            ("Main foreground colour", EditSettingField::NormalFg),
+            ("Main background colour", EditSettingField::NormalBg),
+            ("Selected foreground colour", EditSettingField::SelectionFg),
+            ("Selected background colour", EditSettingField::SelectionBg),
+            ("Active task colour", EditSettingField::Active),
+            ("Title colour", EditSettingField::Title),
+            ("Border colour", EditSettingField::Border),
+        ]),
+        ("Text styles", vec![
+            ("Main text", EditSettingField::NormalMod),
+            ("Selected text", EditSettingField::SelectionMod),
+            ("Active task text", EditSettingField::ActiveMod),
+            ("Title text", EditSettingField::TitleMod),
+            ("Border text", EditSettingField::BorderMod),
+        ]),
+    ];
+
+    let mut section_lines = vec![];
+    let mut value_lines = vec![];
+    // Lines of the selected field and of the heading of its section
+    let mut selected_line = 0;
+    let mut heading_line = 0;
+    for (index, (heading, fields)) in sections.iter().enumerate() {
+        if index > 0 {
+            section_lines.push(Spans::from(vec![Span::styled("", app.settings.default)]));
+            value_lines.push(Spans::from(vec![Span::styled("", app.settings.default)]));
+        }
+        let section_start = section_lines.len();
+        section_lines.push(Spans::from(vec![Span::styled(*heading, app.settings.default.add_modifier(Modifier::UNDERLINED))]));
+        section_lines.push(Spans::from(vec![Span::styled("", app.settings.default)]));
+        value_lines.push(Spans::from(vec![Span::styled("", app.settings.default)]));
+        value_lines.push(Spans::from(vec![Span::styled("", app.settings.default)]));
+
+        for (label, field) in fields {
+            if app.edit_setting == *field {
+                selected_line = section_lines.len() as u16;
+                heading_line = section_start as u16;
+            }
+            let style = if app.edit_setting == *field { app.settings.highlight } else { app.settings.default };
 
-    // Render settings
-    let settings_sections = Paragraph::new(vec![
-        Spans::from(vec![Span::styled("Layout", app.settings.default.add_modifier(Modifier::UNDERLINED))]),
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Split",
-                if app.edit_setting == EditSettingField::Split { app.settings.highlight } else { app.settings.default }
-            )]),
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![Span::styled("Task colours", app.settings.default.add_modifier(Modifier::UNDERLINED))]),
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Main foreground colour",
-                if app.edit_setting == EditSettingField::NormalFg { app.settings.highlight } else { app.settings.default }
-            )]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Main background colour",
-                if app.edit_setting == EditSettingField::NormalBg { app.settings.highlight } else { app.settings.default }
-            )]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Selected foreground colour",
-                if app.edit_setting == EditSettingField::SelectionFg { app.settings.highlight } else { app.settings.default }
-            )]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Selected background colour",
-                if app.edit_setting == EditSettingField::SelectionBg { app.settings.highlight } else { app.settings.default }
-            )]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Active task colour",
-                if app.edit_setting == EditSettingField::Active { app.settings.highlight } else { app.settings.default }
-            )]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Title colour",
-                if app.edit_setting == EditSettingField::Title { app.settings.highlight } else { app.settings.default }
-            )]),
-        Spans::from(vec![
-            Span::styled("  ", app.settings.default),
-            Span::styled(
-                "Border colour",
-                if app.edit_setting == EditSettingField::Border { app.settings.highlight } else { app.settings.default }
-            )]),
-    ])
+            section_lines.push(Spans::from(vec![
+                Span::styled("  ", app.settings.default),
+                Span::styled(*label, style),
+            ]));
+
+            let mut value = setting_value(app, field, style);
+            value.push(Span::styled("    ", app.settings.default));
+            value_lines.push(Spans::from(value));
+        }
+    }
+
+    // Scroll so the selected field is shown, with its heading when it fits
+    let height = hsplit_layout[0].height.saturating_sub(2);
+    if selected_line < app.first_setting_line {
+        app.first_setting_line = heading_line.max((selected_line + 1).saturating_sub(height));
+    } else if selected_line >= app.first_setting_line + height {
+        app.first_setting_line = selected_line + 1 - height;
+    }
+    app.first_setting_line = app.first_setting_line.min((section_lines.len() as u16).saturating_sub(height));
+
+    let settings_sections = Paragraph::new(section_lines)
+        .scroll((app.first_setting_line, 0))
         .alignment(Alignment::Left)
         .block(
             Block::default()
@@ -666,46 +940,8 @@ fn render_settings<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
                 .border_style(border_style)
         );
 
-    let settings_values = Paragraph::new(vec![
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(
-                if app.settings.is_horizontal { "Horizontal" } else { "Vertical" },
-                if app.edit_setting == EditSettingField::Split { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![Span::styled("", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(colour_to_string(app.settings.normal_fg_colour),
-            if app.edit_setting == EditSettingField::NormalFg { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(colour_to_string(app.settings.normal_bg_colour),
-            if app.edit_setting == EditSettingField::NormalBg { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(colour_to_string(app.settings.select_fg_colour),
-            if app.edit_setting == EditSettingField::SelectionFg { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(colour_to_string(app.settings.select_bg_colour),
-            if app.edit_setting == EditSettingField::SelectionBg { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(colour_to_string(app.settings.active_fg_colour),
-            if app.edit_setting == EditSettingField::Active { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(colour_to_string(app.settings.title_fg_colour),
-            if app.edit_setting == EditSettingField::Title { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-        Spans::from(vec![
-            Span::styled(colour_to_string(app.settings.border_colour),
-            if app.edit_setting == EditSettingField::Border { app.settings.highlight } else { app.settings.default }),
-            Span::styled("    ", app.settings.default)]),
-    ])
+    let settings_values = Paragraph::new(value_lines)
+        .scroll((app.first_setting_line, 0))
         .alignment(Alignment::Right)
         .block(
             Block::default()
@@ -723,6 +959,9 @@ fn render_settings<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
         Spans::from(vec![Span::styled("[ ] This task is none of the above, just sitting here calmly", app.settings.default)]),
         Spans::from(vec![Span::styled("[X] This task is selected and active (although there can only be one active one", app.settings.active_highlight)]),
         Spans::from(vec![Span::styled("[ ] This task is none of the above, just sitting here calmly", app.settings.default)]),
+        Spans::from(vec![Span::styled("", app.settings.default)]),
+        Spans::from(vec![Span::styled("Titles look like this", app.settings.title)]),
+        Spans::from(vec![Span::styled("Borders and instructions look like this", app.settings.border)]),
     ])
         .alignment(Alignment::Left)
         .block(
@@ -736,3 +975,60 @@ fn render_settings<B: Backend>(f: &mut Frame<B>, rect: &Rect, app: &mut App) {
     f.render_widget(settings_values, hsplit_layout[1]);
     f.render_widget(example, vsplit_layout[1]);
 }
+
+
+
+// Spans showing the value of a settings field
+fn setting_value<'a>(app: &App, field: &EditSettingField, style: Style) -> Vec<Span<'a>> {
+    let settings = &app.settings;
+    let colour = match field {
+        EditSettingField::Split => {
+            return vec![Span::styled(if settings.is_horizontal { "Horizontal" } else { "Vertical" }, style)];
+        },
+        EditSettingField::AutosaveDelay => {
+            return vec![Span::styled(interval_to_string(settings.autosave_secs), style)];
+        },
+        EditSettingField::Checkpoint => {
+            return vec![Span::styled(interval_to_string(settings.checkpoint_secs), style)];
+        },
+        EditSettingField::CalendarFile => {
+            return vec![Span::styled(if settings.calendar_file { format!("On, {}", formats::CALENDAR_FILE) } else { String::from("Off") }, style)];
+        },
+        EditSettingField::Theme => {
+            // While a theme name is being typed show the input instead of the value
+            if let Some(input) = &app.setting_input {
+                let mut input = input.clone();
+                input.push('_');
+                return vec![Span::styled(input, style)];
+            }
+            return vec![Span::styled(if settings.theme.is_empty() { String::from("Custom") } else { settings.theme.clone() }, style)];
+        },
+        EditSettingField::NormalFg    => settings.normal_fg_colour,
+        EditSettingField::NormalBg    => settings.normal_bg_colour,
+        EditSettingField::SelectionFg => settings.select_fg_colour,
+        EditSettingField::SelectionBg => settings.select_bg_colour,
+        EditSettingField::Active      => settings.active_fg_colour,
+        EditSettingField::Title       => settings.title_fg_colour,
+        EditSettingField::Border      => settings.border_colour,
+        EditSettingField::NormalMod    => return vec![Span::styled(modifier_to_string(settings.normal_modifiers), style)],
+        EditSettingField::SelectionMod => return vec![Span::styled(modifier_to_string(settings.select_modifiers), style)],
+        EditSettingField::ActiveMod    => return vec![Span::styled(modifier_to_string(settings.active_modifiers), style)],
+        EditSettingField::TitleMod     => return vec![Span::styled(modifier_to_string(settings.title_modifiers), style)],
+        EditSettingField::BorderMod    => return vec![Span::styled(modifier_to_string(settings.border_modifiers), style)],
+    };
+
+    // While a colour is being typed show the input instead of the value
+    if app.edit_setting == *field {
+        if let Some(input) = &app.setting_input {
+            let mut input = input.clone();
+            input.push('_');
+            return vec![Span::styled(input, style)];
+        }
+    }
+
+    vec![
+        Span::styled("  ", Style::default().bg(colour)),
+        Span::styled(" ", settings.default),
+        Span::styled(colour_to_string(colour), style),
+    ]
+}
\ No newline at end of file
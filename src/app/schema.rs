@@ -0,0 +1,460 @@
+// ----------------------------------------------------------------------------
+// SCHEMA SUB-MODULE
+// This submodule versions the data files. Every file is saved wrapped with the
+// version of its format and older files are brought up to date on load by a
+// chain of migrations, each one going from a version to the next.
+// Files that can't be loaded are reported with the reason, so they can be
+// restored from a backup or set aside.
+// ----------------------------------------------------------------------------
+
+use crate::error::{Error, Result};
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+pub const VERSION: u64 = 2;
+
+// Files saved before versioning was added have no wrapper and count as version 0
+const UNVERSIONED: u64 = 0;
+
+// Colour and modifier keys of version 0 settings, the styles hold the same values
+const OBSOLETE_SETTINGS_KEYS: &[&str] = &[
+    "normal_fg_colour", "normal_bg_colour", "select_fg_colour", "select_bg_colour",
+    "active_fg_colour", "title_fg_colour", "border_colour",
+    "normal_modifiers", "select_modifiers", "active_modifiers", "title_modifiers", "border_modifiers",
+];
+
+// Files backed up by this process, so the backup holds a file as it was
+// before the session first saved it and autosaves don't write over it
+static BACKED_UP: Mutex<Vec<PathBuf>> = Mutex::new(vec![]);
+
+#[derive(Copy, Clone)]
+pub enum DataFile {
+    Tasks,
+    Archive,
+    Settings,
+}
+
+type Migration = fn(Value) -> Value;
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    version: u64,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+struct RawEnvelope {
+    version: u64,
+    data: Value,
+}
+
+// Read straight from the text so errors point at a line and column
+#[derive(Deserialize)]
+struct TypedEnvelope<T> {
+    #[allow(dead_code)]
+    version: u64,
+    data: T,
+}
+
+// A data file that can't be loaded
+pub struct InvalidFile {
+    pub path: PathBuf,
+    pub error: String,
+    pub backup: Option<PathBuf>,
+}
+
+impl InvalidFile {
+    // Puts the backup in place of the file, the file is kept aside
+    pub fn restore_backup(&self) -> io::Result<()> {
+        if let Some(backup) = &self.backup {
+            self.set_aside()?;
+            fs::copy(backup, &self.path)?;
+        }
+
+        Ok(())
+    }
+
+    // Renames the file so a new one is started, returns where it was moved
+    pub fn set_aside(&self) -> io::Result<PathBuf> {
+        let aside = sibling_path(&self.path, ".corrupt");
+        fs::rename(&self.path, &aside)?;
+
+        Ok(aside)
+    }
+}
+
+impl DataFile {
+    // Migration at index n takes the data from version n to n + 1
+    fn migrations(&self) -> &'static [Migration] {
+        match self {
+            DataFile::Tasks    => &[wrap_only, assign_ids],
+            DataFile::Archive  => &[wrap_only, assign_archive_ids],
+            DataFile::Settings => &[drop_colour_keys, wrap_only],
+        }
+    }
+}
+
+// ---- MIGRATIONS ----
+// Version 1 only added the wrapper, and version 2 left the settings as they were
+fn wrap_only(data: Value) -> Value {
+    data
+}
+
+// Version 1 settings keep the styles alone, the colours are taken from them.
+// Files older than themes have custom colours, not the default theme.
+fn drop_colour_keys(mut data: Value) -> Value {
+    if let Some(map) = data.as_object_mut() {
+        for key in OBSOLETE_SETTINGS_KEYS {
+            map.remove(*key);
+        }
+        map.entry("theme").or_insert_with(|| Value::from(""));
+    }
+
+    data
+}
+
+// Version 2 tasks have ids. Older tasks get one made from when they were
+// created and their title, so every copy of an old file gives the same ids and
+// they can still be matched up.
+fn assign_ids(mut data: Value) -> Value {
+    if let Some(tasks) = data.as_array_mut() {
+        set_missing_ids(tasks, &mut HashMap::new());
+    }
+
+    data
+}
+
+fn assign_archive_ids(mut data: Value) -> Value {
+    let mut seen = HashMap::new();
+    for item in data.as_array_mut().into_iter().flatten() {
+        if let Some(tasks) = item.get_mut("tasks").and_then(Value::as_array_mut) {
+            set_missing_ids(tasks, &mut seen);
+        }
+    }
+
+    data
+}
+
+// Tasks alike are told apart by how many came before them
+fn set_missing_ids(tasks: &mut [Value], seen: &mut HashMap<String, usize>) {
+    for task in tasks.iter_mut().filter_map(Value::as_object_mut) {
+        if task.contains_key("id") {
+            continue;
+        }
+
+        let created_on = task.get("created_on").and_then(Value::as_str).unwrap_or_default();
+        let title = task.get("title").and_then(Value::as_str).unwrap_or_default();
+        let name = format!("{}\n{}", created_on, title);
+
+        let count = seen.entry(name.clone()).or_insert(0);
+        let name = if *count == 0 { name } else { format!("{}\n{}", name, count) };
+        *count += 1;
+
+        let id = Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes());
+        task.insert(String::from("id"), Value::from(id.to_string()));
+    }
+}
+
+// Parses the content of a data file of any known version, returns the data
+// and the version it was saved with
+pub fn parse<T: DeserializeOwned>(content: &str, kind: DataFile) -> Result<(T, u64)> {
+    let value: Value = serde_json::from_str(content)?;
+
+    let (version, mut data) = if value.is_object() && value.get("version").is_some() {
+        let envelope: RawEnvelope = serde_json::from_value(value)?;
+        (envelope.version, envelope.data)
+    } else {
+        (UNVERSIONED, value)
+    };
+
+    if version > VERSION {
+        return Err(Error::Storage(format!("file format version {} is newer than this todo_rust supports ({})", version, VERSION)));
+    }
+
+    if version == VERSION {
+        let envelope: TypedEnvelope<T> = serde_json::from_str(content)?;
+        return Ok((envelope.data, version));
+    }
+
+    for migration in &kind.migrations()[version as usize..] {
+        data = migration(data);
+    }
+
+    Ok((serde_json::from_value(data)?, version))
+}
+
+// Reads a data file, files of older versions are migrated but only saved, and
+// backed up, when the data is
+pub fn load<T: DeserializeOwned>(path: &Path, kind: DataFile) -> Result<T> {
+    let content = fs::read_to_string(path)?;
+
+    Ok(parse(&content, kind)?.0)
+}
+
+// Checks that a data file can be loaded, missing files are fine as they are
+// created on load
+pub fn check<T: DeserializeOwned>(path: &Path, kind: DataFile) -> Option<InvalidFile> {
+    if !path.exists() {
+        return None;
+    }
+
+    let result = fs::read_to_string(path)
+        .map_err(Error::from)
+        .and_then(|content| parse::<T>(&content, kind));
+
+    match result {
+        Ok(_) => None,
+        Err(err) => Some(InvalidFile {
+            path: path.to_path_buf(),
+            error: err.to_string(),
+            backup: latest_backup(path),
+        }),
+    }
+}
+
+pub fn to_vec<T: Serialize>(data: &T) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec_pretty(&Envelope { version: VERSION, data })
+}
+
+// Writes a data file. The first time a process saves a file, the file it
+// replaces is kept as a backup if it was valid. A file of an older version is
+// also kept as it was before its first migration.
+pub fn save<T: Serialize + DeserializeOwned>(path: &Path, data: &T, kind: DataFile) -> Result<()> {
+    let mut backed_up = BACKED_UP.lock().unwrap_or_else(|err| err.into_inner());
+    if !backed_up.iter().any(|p| p == path) {
+        if let Ok(content) = fs::read_to_string(path) {
+            if let Ok((_, version)) = parse::<T>(&content, kind) {
+                let backup = backup_path(path, version);
+                if version < VERSION && !backup.exists() {
+                    write_file(&backup, content.as_bytes())?;
+                }
+                write_file(&sibling_path(path, ".bak"), content.as_bytes())?;
+            }
+        }
+        backed_up.push(path.to_path_buf());
+    }
+
+    write_file(path, &to_vec(data)?)
+}
+
+// Writes a temporary file next to the target and renames it over the target,
+// so a crash or a full disk leaves the old file whole. Links are followed so
+// the file they point to is the one replaced.
+pub fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let temp = sibling_path(&path, ".tmp");
+
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, &path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+
+    Ok(result?)
+}
+
+// Backups sit next to the file, e.g. tasks.json.v0.bak
+fn backup_path(path: &Path, version: u64) -> PathBuf {
+    sibling_path(path, &format!(".v{}.bak", version))
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// The most recent of the backups taken on save and before migrations
+fn latest_backup(path: &Path) -> Option<PathBuf> {
+    let mut backups = vec![sibling_path(path, ".bak")];
+    for version in 0..VERSION {
+        backups.push(backup_path(path, version));
+    }
+
+    backups
+        .into_iter()
+        .filter_map(|backup| {
+            let modified = fs::metadata(&backup).and_then(|m| m.modified()).ok()?;
+            Some((modified, backup))
+        })
+        .max_by_key(|(modified, _): &(SystemTime, PathBuf)| *modified)
+        .map(|(_, backup)| backup)
+}
+
+// An empty folder of its own for each test of the files in the data folder
+#[cfg(test)]
+pub fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("todo_rust-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{ArchiveItem, Settings};
+    use crate::app::task::Task;
+
+    use tui::style::Color;
+
+    // Tasks as saved before versioning, without the wrapper and without ids
+    const V0_TASKS: &str = r#"[
+        {"title": "Test 1", "description": "", "is_done": false, "is_active": false, "is_selected": true,
+         "elapsed_time": {"secs": 90, "nanos": 0}, "created_on": "2023-01-09T07:06:12.390235Z"},
+        {"title": "Test 1", "description": "", "is_done": false, "is_active": false, "is_selected": false,
+         "elapsed_time": {"secs": 0, "nanos": 0}, "created_on": "2023-01-09T07:06:12.390235Z"},
+        {"title": "Test 2", "description": "", "is_done": true, "is_active": false, "is_selected": false,
+         "elapsed_time": {"secs": 0, "nanos": 0}, "created_on": "2023-01-09T07:06:16.412815Z"}
+    ]"#;
+
+    const V0_ARCHIVE: &str = r#"[
+        {"date": "2023-01-10T18:00:00Z", "tasks": [
+            {"title": "Done", "description": "", "is_done": true, "is_active": false, "is_selected": false,
+             "elapsed_time": {"secs": 60, "nanos": 0}, "created_on": "2023-01-09T07:06:20.411758Z"}
+        ]}
+    ]"#;
+
+    // Settings as saved before versioning, with the colours next to the styles
+    const V0_SETTINGS: &str = r#"{
+        "is_horizontal": false,
+        "default": {"fg": "Yellow", "bg": "Black", "add_modifier": {"bits": 0}, "sub_modifier": {"bits": 0}},
+        "highlight": {"fg": "Yellow", "bg": "Blue", "add_modifier": {"bits": 0}, "sub_modifier": {"bits": 0}},
+        "active_normal": {"fg": "White", "bg": "Black", "add_modifier": {"bits": 0}, "sub_modifier": {"bits": 0}},
+        "active_highlight": {"fg": "White", "bg": "Blue", "add_modifier": {"bits": 0}, "sub_modifier": {"bits": 0}},
+        "title": {"fg": "Cyan", "bg": "Black", "add_modifier": {"bits": 0}, "sub_modifier": {"bits": 0}},
+        "border": {"fg": "Cyan", "bg": "Black", "add_modifier": {"bits": 0}, "sub_modifier": {"bits": 0}},
+        "normal_fg_colour": "Yellow",
+        "normal_bg_colour": "Black",
+        "select_fg_colour": "Yellow",
+        "select_bg_colour": "Blue",
+        "active_fg_colour": "White",
+        "title_fg_colour": "Cyan",
+        "border_colour": "Cyan"
+    }"#;
+
+    #[test]
+    fn version_0_tasks_get_the_same_ids_every_time() {
+        let (tasks, version) = parse::<Vec<Task>>(V0_TASKS, DataFile::Tasks).unwrap();
+        let (again, _) = parse::<Vec<Task>>(V0_TASKS, DataFile::Tasks).unwrap();
+
+        assert_eq!(version, 0);
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[0].elapsed_time.as_secs(), 90);
+        assert!(tasks[2].is_done);
+
+        let ids: Vec<Uuid> = tasks.iter().map(|t| t.id).collect();
+        assert_eq!(ids, again.iter().map(|t| t.id).collect::<Vec<_>>());
+        // Tasks alike still have ids of their own
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn version_1_tasks_without_ids_get_them() {
+        let v1 = format!(r#"{{"version": 1, "data": {}}}"#, V0_TASKS);
+
+        let (tasks, version) = parse::<Vec<Task>>(&v1, DataFile::Tasks).unwrap();
+        let (v0_tasks, _) = parse::<Vec<Task>>(V0_TASKS, DataFile::Tasks).unwrap();
+
+        assert_eq!(version, 1);
+        assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<_>>(), v0_tasks.iter().map(|t| t.id).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ids_in_the_file_are_kept() {
+        let id = Uuid::new_v4();
+        let v1 = format!(r#"{{"version": 1, "data": [{{"id": "{}", "title": "Test"}}]}}"#, id);
+
+        let (tasks, _) = parse::<Vec<Task>>(&v1, DataFile::Tasks).unwrap();
+
+        assert_eq!(tasks[0].id, id);
+    }
+
+    #[test]
+    fn version_0_archive_is_migrated() {
+        let (archive, version) = parse::<Vec<ArchiveItem>>(V0_ARCHIVE, DataFile::Archive).unwrap();
+        let (again, _) = parse::<Vec<ArchiveItem>>(V0_ARCHIVE, DataFile::Archive).unwrap();
+
+        assert_eq!(version, 0);
+        assert_eq!(archive[0].tasks[0].title, "Done");
+        assert_eq!(archive[0].tasks[0].id, again[0].tasks[0].id);
+    }
+
+    #[test]
+    fn version_0_settings_are_loaded() {
+        let dir = temp_dir();
+        let path = dir.join("settings.json");
+        fs::write(&path, V0_SETTINGS).unwrap();
+
+        let settings = Settings::load(&path).unwrap();
+
+        assert!(!settings.is_horizontal);
+        assert_eq!(settings.highlight.bg, Some(Color::Blue));
+        assert_eq!(settings.normal_fg_colour, Color::Yellow);
+        assert_eq!(settings.border_colour, Color::Cyan);
+        // Older files have custom colours, and the settings added since keep their defaults
+        assert_eq!(settings.theme, "");
+        assert_eq!(settings.autosave_secs, Settings::default_settings().autosave_secs);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let newer = format!(r#"{{"version": {}, "data": []}}"#, VERSION + 1);
+
+        assert!(parse::<Vec<Task>>(&newer, DataFile::Tasks).is_err());
+    }
+
+    #[test]
+    fn version_0_files_are_backed_up_when_saved_not_when_loaded() {
+        let dir = temp_dir();
+        let path = dir.join("tasks.json");
+        fs::write(&path, V0_TASKS).unwrap();
+
+        let tasks: Vec<Task> = load(&path, DataFile::Tasks).unwrap();
+        assert!(!backup_path(&path, 0).exists());
+
+        save(&path, &tasks, DataFile::Tasks).unwrap();
+        assert_eq!(fs::read_to_string(backup_path(&path, 0)).unwrap(), V0_TASKS);
+
+        let (saved, version) = parse::<Vec<Task>>(&fs::read_to_string(&path).unwrap(), DataFile::Tasks).unwrap();
+        assert_eq!(version, VERSION);
+        assert_eq!(saved.iter().map(|t| t.id).collect::<Vec<_>>(), tasks.iter().map(|t| t.id).collect::<Vec<_>>());
+        assert!(!sibling_path(&path, ".tmp").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_backup_is_the_file_as_the_session_found_it() {
+        let dir = temp_dir();
+        let path = dir.join("tasks.json");
+        let (tasks, _) = parse::<Vec<Task>>(V0_TASKS, DataFile::Tasks).unwrap();
+        let found = to_vec(&tasks).unwrap();
+        fs::write(&path, &found).unwrap();
+
+        save(&path, &tasks[..2].to_vec(), DataFile::Tasks).unwrap();
+        save(&path, &tasks[..1].to_vec(), DataFile::Tasks).unwrap();
+
+        assert_eq!(fs::read(sibling_path(&path, ".bak")).unwrap(), found);
+        let (saved, _) = parse::<Vec<Task>>(&fs::read_to_string(&path).unwrap(), DataFile::Tasks).unwrap();
+        assert_eq!(saved.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
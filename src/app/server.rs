@@ -0,0 +1,491 @@
+// ----------------------------------------------------------------------------
+// SERVER SUB-MODULE
+// This submodule serves the tasks over a local HTTP API with JSON bodies, for
+// editor plugins and dashboards. The server holds the lock of the data folder
+// like the app does and answers one request at a time, saving after every
+// change, so the files only ever have one writer:
+//   GET    /tasks                   the task list, ?done=true|false
+//   POST   /tasks                   add a task, {"title", "description", "priority", "due"}
+//   GET    /tasks/{id}              one task
+//   PATCH  /tasks/{id}              change the fields given, null clears priority and due
+//   DELETE /tasks/{id}              delete a task
+//   POST   /tasks/{id}/complete     mark a task done, stopping its timer
+//   POST   /tasks/{id}/start        start the timer of a task, stopping any other
+//   POST   /tasks/{id}/stop         stop the timer of a task
+//   POST   /archive                 archive the done tasks, returns them
+//   GET    /archive                 archive batches, ?from=YYYY-MM-DD&to=YYYY-MM-DD&q=text
+// Web pages open in a browser can send requests to local addresses too, so
+// only requests for the bound address or localhost are answered, and changes
+// need a JSON content type, which pages can't send without asking first.
+// ----------------------------------------------------------------------------
+
+use super::{today, ArchiveItem};
+use super::task::Task;
+use super::schema::{self, DataFile};
+use super::sync;
+
+use crate::error::{Error, Result};
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDate, Utc};
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+// How often the time of a running timer is saved
+const CHECKPOINT_TIME: Duration = Duration::from_secs(60);
+
+// Names the server can always be reached by, besides the bound address
+const LOCAL_HOSTS: &[&str] = &["localhost", "127.0.0.1", "[::1]"];
+
+// Bodies larger than this are refused
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+// A failed request, answered with its status and {"error": message}
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> ApiError {
+        ApiError { status, message: message.into() }
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(err: Error) -> ApiError {
+        ApiError::new(500, err.to_string())
+    }
+}
+
+type Reply = std::result::Result<(u16, Value), ApiError>;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewTask {
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    priority: Option<char>,
+    #[serde(default)]
+    due: Option<NaiveDate>,
+}
+
+// Fields left out stay as they are
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TaskChanges {
+    title: Option<String>,
+    description: Option<String>,
+    is_done: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    priority: Option<Option<char>>,
+    #[serde(default, deserialize_with = "present")]
+    due: Option<Option<NaiveDate>>,
+}
+
+// Tells a null value, which clears the field, from one that was left out
+fn present<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<T>, D::Error> {
+    T::deserialize(deserializer).map(Some)
+}
+
+// The tasks and the archive, owned by the server while it runs
+struct Store {
+    data_dir: PathBuf,
+    tasks: Vec<Task>,
+    archive: Vec<ArchiveItem>,
+    git_sync: bool,
+    // When the time of the running timer was last added
+    last_tick: Instant,
+    last_save: Instant,
+    // Host headers requests may have
+    hosts: Vec<String>,
+}
+
+// Serves the tasks of the data folder at the address until the process is stopped
+pub fn serve(data_dir: &Path, address: &str) -> Result<()> {
+    let mut store = Store::load(data_dir, address)?;
+    let server = Server::http(address).map_err(|err| Error::Storage(format!("could not listen on {}: {}", address, err)))?;
+
+    println!("Serving the tasks in {} on http://{}", data_dir.display(), address);
+
+    loop {
+        // Requests may keep coming, the checkpoint is kept either way
+        if let Some(request) = server.recv_timeout(CHECKPOINT_TIME.saturating_sub(store.last_save.elapsed()))? {
+            store.answer(request);
+        }
+        store.checkpoint();
+    }
+}
+
+impl Store {
+    fn load(data_dir: &Path, address: &str) -> Result<Store> {
+        let tasks_path = data_dir.join("tasks.json");
+        let archive_path = data_dir.join("archive.json");
+
+        // Browsers leave the port out of the host header when it is the default one
+        let (host, port) = address.rsplit_once(':').unwrap_or((address, ""));
+        let hosts = LOCAL_HOSTS
+            .iter()
+            .chain([&host])
+            .flat_map(|host| [host.to_string(), format!("{}:{}", host, port)])
+            .collect();
+
+        Ok(Store {
+            data_dir: data_dir.to_path_buf(),
+            tasks: if tasks_path.exists() { schema::load(&tasks_path, DataFile::Tasks)? } else { vec![] },
+            archive: if archive_path.exists() { schema::load(&archive_path, DataFile::Archive)? } else { vec![] },
+            git_sync: sync::is_enabled(data_dir),
+            last_tick: Instant::now(),
+            last_save: Instant::now(),
+            hosts,
+        })
+    }
+
+    fn save(&mut self) -> Result<()> {
+        schema::save(&self.data_dir.join("tasks.json"), &self.tasks, DataFile::Tasks)?;
+        schema::save(&self.data_dir.join("archive.json"), &self.archive, DataFile::Archive)?;
+        self.last_save = Instant::now();
+
+        Ok(())
+    }
+
+    // Saves the time of the running timer once it wasn't saved for a while
+    fn checkpoint(&mut self) {
+        if self.last_save.elapsed() < CHECKPOINT_TIME {
+            return;
+        }
+
+        if self.tasks.iter().any(|t| t.is_active) {
+            self.tick();
+            if let Err(err) = self.save() {
+                eprintln!("todo_rust: could not save: {}", err);
+            }
+        }
+        // Waits for the next round either way instead of trying at once
+        self.last_save = Instant::now();
+    }
+
+    // Adds the time since the last tick to the running timer, as the app does
+    fn tick(&mut self) {
+        let elapsed = self.last_tick.elapsed();
+        self.last_tick = Instant::now();
+
+        let today = today();
+        for task in self.tasks.iter_mut().filter(|t| t.is_active) {
+            task.add_time(today, elapsed);
+        }
+    }
+
+    fn answer(&mut self, mut request: Request) {
+        self.tick();
+
+        let mut body = String::new();
+        let read = request.as_reader().take(MAX_BODY_BYTES).read_to_string(&mut body);
+
+        let reply = match read {
+            Ok(_) => self.check_headers(&request).and_then(|_| self.route(request.method(), request.url(), &body)),
+            Err(err) => Err(ApiError::new(400, format!("could not read the body: {}", err))),
+        };
+
+        let (status, value) = match reply {
+            Ok(reply) => reply,
+            Err(err) => (err.status, json!({ "error": err.message })),
+        };
+
+        let content_type = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+        let response = Response::from_string(value.to_string()).with_status_code(status).with_header(content_type);
+        if let Err(err) = request.respond(response) {
+            eprintln!("todo_rust: could not answer a request: {}", err);
+        }
+    }
+
+    // Refuses requests sent to other names, as pages do through DNS rebinding,
+    // and changes that aren't JSON, as forms of other pages are
+    fn check_headers(&self, request: &Request) -> std::result::Result<(), ApiError> {
+        let header = |name: &'static str| request.headers().iter().find(|h| h.field.equiv(name)).map(|h| h.value.as_str());
+
+        let host = header("Host").unwrap_or("");
+        if !self.hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+            return Err(ApiError::new(403, format!("requests for host '{}' are not answered", host)));
+        }
+
+        let json = header("Content-Type")
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case("application/json"));
+        if *request.method() != Method::Get && !json {
+            return Err(ApiError::new(415, "changes need the Content-Type application/json"));
+        }
+
+        Ok(())
+    }
+
+    fn route(&mut self, method: &Method, url: &str, body: &str) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let query = parse_query(query);
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        // What the files hold, to go back to when a change can't be saved
+        let saved = (*method != Method::Get).then(|| (self.tasks.clone(), self.archive.clone()));
+
+        let reply = match (method, segments.as_slice()) {
+            (Method::Get, ["tasks"]) => self.list_tasks(&query),
+            (Method::Post, ["tasks"]) => self.create_task(body),
+            (Method::Get, ["tasks", id]) => self.get_task(id),
+            (Method::Patch, ["tasks", id]) => self.update_task(id, body),
+            (Method::Delete, ["tasks", id]) => self.delete_task(id),
+            (Method::Post, ["tasks", id, "complete"]) => self.complete_task(id),
+            (Method::Post, ["tasks", id, "start"]) => self.start_timer(id),
+            (Method::Post, ["tasks", id, "stop"]) => self.stop_timer(id),
+            (Method::Post, ["archive"]) => self.archive_done(),
+            (Method::Get, ["archive"]) => self.query_archive(&query),
+            (_, ["tasks"] | ["tasks", _] | ["tasks", _, "complete" | "start" | "stop"] | ["archive"]) => {
+                Err(ApiError::new(405, format!("{} is not allowed on {}", method, path)))
+            },
+            _ => Err(ApiError::new(404, format!("no such endpoint: {}", path))),
+        }?;
+
+        if let Some((tasks, archive)) = saved {
+            if let Err(err) = self.save() {
+                self.tasks = tasks;
+                self.archive = archive;
+                return Err(ApiError::new(500, format!("could not save, the change was undone: {}", err)));
+            }
+
+            // The change is saved either way, the commit is tried again with the next one
+            if self.git_sync {
+                if let Err(err) = sync::commit(&self.data_dir) {
+                    eprintln!("todo_rust: could not commit the tasks: {}", err);
+                }
+            }
+        }
+
+        Ok(reply)
+    }
+
+    // ---- TASKS ----
+    fn list_tasks(&self, query: &[(String, String)]) -> Reply {
+        let done = match find_param(query, "done") {
+            Some(done) => Some(done.parse::<bool>().map_err(|_| ApiError::new(400, "done should be true or false"))?),
+            None => None,
+        };
+
+        let tasks: Vec<&Task> = self.tasks.iter().filter(|t| done.is_none_or(|done| t.is_done == done)).collect();
+
+        Ok((200, json!(tasks)))
+    }
+
+    fn create_task(&mut self, body: &str) -> Reply {
+        let new: NewTask = parse_body(body)?;
+        if new.title.trim().is_empty() {
+            return Err(ApiError::new(400, "the title can't be empty"));
+        }
+
+        let task = Task {
+            title: new.title,
+            description: new.description,
+            priority: check_priority(new.priority)?,
+            due: new.due,
+            ..Task::default()
+        };
+        self.tasks.push(task.clone());
+
+        Ok((201, json!(task)))
+    }
+
+    fn get_task(&self, id: &str) -> Reply {
+        let index = self.find_task(id)?;
+
+        Ok((200, json!(self.tasks[index])))
+    }
+
+    fn update_task(&mut self, id: &str, body: &str) -> Reply {
+        let index = self.find_task(id)?;
+        let changes: TaskChanges = parse_body(body)?;
+
+        // Every field is checked before any is changed, a bad one changes nothing
+        if changes.title.as_ref().is_some_and(|title| title.trim().is_empty()) {
+            return Err(ApiError::new(400, "the title can't be empty"));
+        }
+        let priority = changes.priority.map(check_priority).transpose()?;
+
+        let task = &mut self.tasks[index];
+        if let Some(title) = changes.title {
+            task.title = title;
+        }
+        if let Some(description) = changes.description {
+            task.description = description;
+        }
+        if let Some(priority) = priority {
+            task.priority = priority;
+        }
+        if let Some(due) = changes.due {
+            task.due = due;
+        }
+        if let Some(is_done) = changes.is_done {
+            task.is_done = is_done;
+            // Done tasks don't track time, as in the app
+            if is_done {
+                task.is_active = false;
+            }
+        }
+
+        Ok((200, json!(task)))
+    }
+
+    fn delete_task(&mut self, id: &str) -> Reply {
+        let index = self.find_task(id)?;
+        let task = self.tasks.remove(index);
+
+        Ok((200, json!(task)))
+    }
+
+    fn complete_task(&mut self, id: &str) -> Reply {
+        let index = self.find_task(id)?;
+        let task = &mut self.tasks[index];
+        task.is_done = true;
+        task.is_active = false;
+
+        Ok((200, json!(task)))
+    }
+
+    // ---- TIMER ----
+    fn start_timer(&mut self, id: &str) -> Reply {
+        let index = self.find_task(id)?;
+        if self.tasks[index].is_done {
+            return Err(ApiError::new(409, "done tasks can't be timed"));
+        }
+
+        // One timer runs at a time
+        for task in &mut self.tasks {
+            task.is_active = false;
+        }
+        self.tasks[index].is_active = true;
+
+        Ok((200, json!(self.tasks[index])))
+    }
+
+    fn stop_timer(&mut self, id: &str) -> Reply {
+        let index = self.find_task(id)?;
+        self.tasks[index].is_active = false;
+
+        Ok((200, json!(self.tasks[index])))
+    }
+
+    // ---- ARCHIVE ----
+    // Moves the done tasks to a new archive batch, as archiving in the app does
+    fn archive_done(&mut self) -> Reply {
+        let (done, open): (Vec<Task>, Vec<Task>) = self.tasks.drain(..).partition(|t| t.is_done);
+        self.tasks = open;
+
+        if !done.is_empty() {
+            self.archive.push(ArchiveItem { date: Utc::now(), tasks: done.clone() });
+        }
+
+        Ok((200, json!(done)))
+    }
+
+    // Archive batches between two days, with the tasks that contain a text
+    fn query_archive(&self, query: &[(String, String)]) -> Reply {
+        let from = find_param(query, "from").map(parse_date).transpose()?;
+        let to = find_param(query, "to").map(parse_date).transpose()?;
+        let text = find_param(query, "q").map(str::to_lowercase);
+
+        let batches: Vec<Value> = self.archive
+            .iter()
+            .filter(|a| {
+                let day = a.date.with_timezone(&Local).date_naive();
+                from.is_none_or(|from| day >= from) && to.is_none_or(|to| day <= to)
+            })
+            .filter_map(|a| {
+                let tasks: Vec<&Task> = a.tasks
+                    .iter()
+                    .filter(|t| text.as_ref().is_none_or(|text| {
+                        t.title.to_lowercase().contains(text) || t.description.to_lowercase().contains(text)
+                    }))
+                    .collect();
+
+                (!tasks.is_empty() || text.is_none()).then(|| json!({ "date": a.date, "tasks": tasks }))
+            })
+            .collect();
+
+        Ok((200, json!(batches)))
+    }
+
+    fn find_task(&self, id: &str) -> std::result::Result<usize, ApiError> {
+        let id = Uuid::parse_str(id).map_err(|_| ApiError::new(400, format!("{} is not a task id", id)))?;
+
+        self.tasks
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| ApiError::new(404, format!("no task with id {}", id)))
+    }
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a str) -> std::result::Result<T, ApiError> {
+    serde_json::from_str(body).map_err(|err| ApiError::new(400, format!("invalid body: {}", err)))
+}
+
+fn check_priority(priority: Option<char>) -> std::result::Result<Option<char>, ApiError> {
+    match priority {
+        Some(p) if !p.is_ascii_uppercase() => Err(ApiError::new(400, "the priority should be a letter from A to Z")),
+        _ => Ok(priority),
+    }
+}
+
+fn parse_date(text: &str) -> std::result::Result<NaiveDate, ApiError> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").map_err(|_| ApiError::new(400, format!("{} is not a date like 2024-03-01", text)))
+}
+
+// ---- QUERY STRINGS ----
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (key, value) = p.split_once('=').unwrap_or((p, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn find_param<'a>(query: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    query.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+// Undoes %XX escapes, + being a space
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escaped = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            },
+            (b'+', _) => {
+                decoded.push(b' ');
+                index += 1;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            },
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
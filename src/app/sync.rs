@@ -0,0 +1,319 @@
+// ----------------------------------------------------------------------------
+// SYNC SUB-MODULE
+// This submodule keeps the data folder in a git repository. The tasks are
+// committed when saved with the save key and on every sync, which happens when
+// the app starts and quits, and syncing pulls from and pushes to the origin
+// remote. Autosaves aren't committed, that would make a commit a minute.
+// When both sides have new commits the files aren't merged as text, the tasks
+// are merged one by one using their ids, see the merge submodule.
+// ----------------------------------------------------------------------------
+
+use super::ArchiveItem;
+use super::merge;
+use super::task::Task;
+use super::schema::{self, DataFile};
+
+use crate::error::{Error, Result};
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+// ---- CONSTANTS ----
+const DATA_FILES: &[&str] = &["tasks.json", "archive.json"];
+
+// Only the data files are kept, backups and settings stay out of the repository
+const GITIGNORE: &str = "*\n!.gitignore\n!tasks.json\n!archive.json\n";
+
+const DEFAULT_BRANCH: &str = "main";
+const REMOTE: &str = "origin";
+
+// Used for commits when git has no identity set up
+const USER_NAME: &str = "todo_rust";
+const USER_EMAIL: &str = "todo_rust@localhost";
+
+// Whether the data folder is a git repository
+pub fn is_enabled(data_dir: &Path) -> bool {
+    data_dir.join(".git").exists()
+}
+
+// Turns the data folder into a repository, or changes the remote of one,
+// and syncs with the remote if there is one
+pub fn init(data_dir: &Path, remote: Option<&str>) -> Result<()> {
+    if !is_enabled(data_dir) {
+        git(data_dir, &["init", "--quiet"])?;
+        git(data_dir, &["symbolic-ref", "HEAD", &format!("refs/heads/{}", DEFAULT_BRANCH)])?;
+    }
+
+    fs::write(data_dir.join(".gitignore"), GITIGNORE)?;
+
+    if try_git(data_dir, &["config", "user.email"])?.is_none() {
+        git(data_dir, &["config", "user.name", USER_NAME])?;
+        git(data_dir, &["config", "user.email", USER_EMAIL])?;
+    }
+
+    if let Some(remote) = remote {
+        if has_remote(data_dir)? {
+            git(data_dir, &["remote", "set-url", REMOTE, remote])?;
+        } else {
+            git(data_dir, &["remote", "add", REMOTE, remote])?;
+        }
+    }
+
+    sync(data_dir)
+}
+
+// Commits the data files if they changed since the last commit
+pub fn commit(data_dir: &Path) -> Result<()> {
+    let mut add = vec!["add", "--", ".gitignore"];
+    add.extend(DATA_FILES.iter().filter(|f| data_dir.join(f).exists()));
+    git(data_dir, &add)?;
+
+    // A repository without commits gets its first one even with nothing in it
+    let has_head = try_git(data_dir, &["rev-parse", "--verify", "--quiet", "HEAD"])?.is_some();
+    let unchanged = try_git(data_dir, &["diff", "--cached", "--quiet"])?.is_some();
+    if has_head && unchanged {
+        return Ok(());
+    }
+
+    git(data_dir, &["commit", "--quiet", "--allow-empty", "-m", "Update tasks"])?;
+
+    Ok(())
+}
+
+// Commits, takes in the commits of the remote and sends ours to it
+pub fn sync(data_dir: &Path) -> Result<()> {
+    commit(data_dir)?;
+
+    if !has_remote(data_dir)? {
+        return Ok(());
+    }
+
+    pull(data_dir)?;
+
+    // Someone may have pushed since the pull, then theirs are merged in again
+    if try_push(data_dir)?.is_none() {
+        pull(data_dir)?;
+        push(data_dir)?;
+    }
+
+    Ok(())
+}
+
+// Brings in the commits of the remote branch, merging the tasks when both sides
+// have new ones
+fn pull(data_dir: &Path) -> Result<()> {
+    git(data_dir, &["fetch", "--quiet", REMOTE])?;
+
+    let theirs = format!("{}/{}", REMOTE, branch(data_dir)?);
+    if try_git(data_dir, &["rev-parse", "--verify", "--quiet", &theirs])?.is_none() {
+        // Nothing was pushed yet
+        return Ok(());
+    }
+
+    if try_git(data_dir, &["merge-base", "--is-ancestor", &theirs, "HEAD"])?.is_some() {
+        return Ok(());
+    }
+
+    if try_git(data_dir, &["merge-base", "--is-ancestor", "HEAD", &theirs])?.is_some() {
+        git(data_dir, &["merge", "--quiet", "--ff-only", &theirs])?;
+        return Ok(());
+    }
+
+    // Folders that started apart have nothing in common
+    let base = try_git(data_dir, &["merge-base", "HEAD", &theirs])?;
+    let base = match base {
+        Some(commit) => read_commit(data_dir, commit.trim())?,
+        None => (vec![], vec![]),
+    };
+    let ours = read_commit(data_dir, "HEAD")?;
+    let their_data = read_commit(data_dir, &theirs)?;
+
+    // Fields changed on both sides keep ours, there is no one to ask here
+    let (tasks, archive) = merge::merge(
+        (&base.0, &base.1),
+        (&ours.0, &ours.1),
+        (&their_data.0, &their_data.1),
+    ).finish();
+
+    commit_merge(data_dir, &tasks, &archive, &theirs)
+}
+
+// Makes the merge commit from the merged data, with both sides as parents, and
+// only then writes the files. A merge that fails leaves the folder as it was.
+fn commit_merge(data_dir: &Path, tasks: &Vec<Task>, archive: &Vec<ArchiveItem>, theirs: &str) -> Result<()> {
+    let ours = git(data_dir, &["rev-parse", "HEAD"])?.trim().to_string();
+
+    // Our tree with the data files in it replaced
+    let mut tree: Vec<String> = git(data_dir, &["ls-tree", "HEAD"])?
+        .lines()
+        .filter(|entry| !DATA_FILES.iter().any(|file| entry.ends_with(&format!("\t{}", file))))
+        .map(String::from)
+        .collect();
+    for (file, content) in [("tasks.json", schema::to_vec(tasks)?), ("archive.json", schema::to_vec(archive)?)] {
+        let blob = git_with_input(data_dir, &["hash-object", "-w", "--stdin"], &content)?;
+        tree.push(format!("100644 blob {}\t{}", blob.trim(), file));
+    }
+    let tree = git_with_input(data_dir, &["mktree"], tree.join("\n").as_bytes())?;
+
+    let message = format!("Merge tasks from {}", theirs);
+    let merge = git(data_dir, &["commit-tree", tree.trim(), "-p", &ours, "-p", theirs, "-m", &message])?;
+    git(data_dir, &["update-ref", "HEAD", merge.trim(), &ours])?;
+
+    let written = schema::save(&data_dir.join("tasks.json"), tasks, DataFile::Tasks)
+        .and_then(|_| schema::save(&data_dir.join("archive.json"), archive, DataFile::Archive));
+    if let Err(err) = written {
+        // Back to our commit, the next sync merges again
+        git(data_dir, &["update-ref", "HEAD", &ours])?;
+        return Err(err);
+    }
+
+    // The files already match the merge, the index is brought up to it
+    git(data_dir, &["reset", "--quiet"])?;
+
+    Ok(())
+}
+
+fn push(data_dir: &Path) -> Result<()> {
+    match try_push(data_dir)? {
+        Some(_) => Ok(()),
+        None => Err(Error::Storage(format!("git: the {} remote refused the push", REMOTE))),
+    }
+}
+
+fn try_push(data_dir: &Path) -> Result<Option<String>> {
+    try_git(data_dir, &["push", "--quiet", "--set-upstream", REMOTE, &branch(data_dir)?])
+}
+
+fn branch(data_dir: &Path) -> Result<String> {
+    Ok(git(data_dir, &["symbolic-ref", "--short", "HEAD"])?.trim().to_string())
+}
+
+fn has_remote(data_dir: &Path) -> Result<bool> {
+    Ok(try_git(data_dir, &["remote", "get-url", REMOTE])?.is_some())
+}
+
+// The tasks and the archive as they were in a commit, missing files are empty
+fn read_commit(data_dir: &Path, commit: &str) -> Result<(Vec<Task>, Vec<ArchiveItem>)> {
+    let tasks = match try_git(data_dir, &["show", &format!("{}:tasks.json", commit)])? {
+        Some(content) => schema::parse(&content, DataFile::Tasks)?.0,
+        None => vec![],
+    };
+    let archive = match try_git(data_dir, &["show", &format!("{}:archive.json", commit)])? {
+        Some(content) => schema::parse(&content, DataFile::Archive)?.0,
+        None => vec![],
+    };
+
+    Ok((tasks, archive))
+}
+
+// Runs git in the data folder, failing with what it printed
+fn git(data_dir: &Path, args: &[&str]) -> Result<String> {
+    stdout_of(run(data_dir, args)?, args)
+}
+
+// Runs git in the data folder with the given input
+fn git_with_input(data_dir: &Path, args: &[&str], input: &[u8]) -> Result<String> {
+    let output = command(data_dir, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            // Dropping the input closes it, so git knows it has all of it
+            child.stdin.take().map_or(Ok(()), |mut stdin| stdin.write_all(input))?;
+            child.wait_with_output()
+        })
+        .map_err(|err| Error::Storage(format!("could not run git: {}", err)))?;
+
+    stdout_of(output, args)
+}
+
+fn stdout_of(output: Output, args: &[&str]) -> Result<String> {
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::Storage(format!("git {}: {}", args[0], message)));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Runs git in the data folder, None when it fails
+fn try_git(data_dir: &Path, args: &[&str]) -> Result<Option<String>> {
+    let output = run(data_dir, args)?;
+
+    Ok(output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string()))
+}
+
+fn run(data_dir: &Path, args: &[&str]) -> Result<Output> {
+    command(data_dir, args)
+        .output()
+        .map_err(|err| Error::Storage(format!("could not run git: {}", err)))
+}
+
+fn command(data_dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .current_dir(data_dir)
+        // Never wait for a password, the terminal may be taken by the app
+        .env("GIT_TERMINAL_PROMPT", "0");
+
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::schema::temp_dir;
+
+    // Titles of the tasks in the folder, in order of title
+    fn tasks_in(data_dir: &Path) -> Vec<String> {
+        let tasks: Vec<Task> = schema::load(&data_dir.join("tasks.json"), DataFile::Tasks).unwrap();
+        let mut titles: Vec<String> = tasks.into_iter().map(|t| t.title).collect();
+        titles.sort();
+        titles
+    }
+
+    fn add_task(data_dir: &Path, title: &str) {
+        let path = data_dir.join("tasks.json");
+        let mut tasks: Vec<Task> = if path.exists() { schema::load(&path, DataFile::Tasks).unwrap() } else { vec![] };
+        tasks.push(Task { title: String::from(title), ..Task::default() });
+        schema::save(&path, &tasks, DataFile::Tasks).unwrap();
+    }
+
+    #[test]
+    fn folders_that_both_changed_are_merged_through_the_remote() {
+        let root = temp_dir();
+        let remote = root.join("remote.git");
+        let (first, second) = (root.join("first"), root.join("second"));
+        for dir in [&remote, &first, &second] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        git(&remote, &["init", "--quiet", "--bare"]).unwrap();
+        let remote = remote.to_string_lossy().to_string();
+
+        add_task(&first, "First");
+        init(&first, Some(&remote)).unwrap();
+        // Started apart, the histories have nothing in common
+        add_task(&second, "Second");
+        init(&second, Some(&remote)).unwrap();
+
+        add_task(&first, "Third");
+        sync(&first).unwrap();
+        sync(&second).unwrap();
+
+        assert_eq!(tasks_in(&first), ["First", "Second", "Third"]);
+        assert_eq!(tasks_in(&second), tasks_in(&first));
+
+        // The merge commit holds what was written, and nothing is left to commit
+        let parents = git(&first, &["rev-list", "--parents", "-n", "1", "HEAD"]).unwrap();
+        assert_eq!(parents.split_whitespace().count(), 3);
+        let committed = git(&first, &["show", "HEAD:tasks.json"]).unwrap();
+        assert_eq!(committed, fs::read_to_string(first.join("tasks.json")).unwrap());
+        assert_eq!(git(&first, &["status", "--porcelain"]).unwrap(), "");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,11 +1,28 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 
 use serde::{Serialize, Deserialize};
 
+use uuid::Uuid;
+
+// ---- CONSTANTS ----
+// Time added this soon after a session ends carries it on instead of starting another
+const SESSION_GAP_SECS: i64 = 5;
+
+// A stretch of time the timer ran for
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Session {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Task {
+    // Stays the same across copies of the files, so they can be matched up
+    pub id: Uuid,
     pub title: String,
     pub description: String,
     pub is_done: bool,
@@ -13,34 +30,93 @@ pub struct Task {
     pub is_selected: bool,
     pub elapsed_time: Duration,
     pub created_on: DateTime<Utc>,
+    // Priority letter from A to Z, A being the highest
+    pub priority: Option<char>,
+    pub due: Option<NaiveDate>,
+    // Time tracked on each day, in local dates
+    pub daily_time: BTreeMap<NaiveDate, Duration>,
+    // When the time was tracked, files from before sessions only have the totals
+    pub sessions: Vec<Session>,
+}
+
+impl Default for Task {
+    fn default() -> Task {
+        Task {
+            id: Uuid::new_v4(),
+            title: String::from(""),
+            description: String::from(""),
+            is_done: false,
+            is_active: false,
+            is_selected: false,
+            elapsed_time: Duration::from_secs(0),
+            created_on: Utc::now(),
+            priority: None,
+            due: None,
+            daily_time: BTreeMap::new(),
+            sessions: vec![],
+        }
+    }
 }
 
 impl Task {
     pub fn get_time_str(&self) -> String {
-        let mut time_str = String::from("");
-
-        if self.elapsed_time.as_secs() < 60 {
-            time_str.push_str("< 1 min");
-        } else {
-            let hours: u64 = (self.elapsed_time.as_secs() as f64 / 3600.0).floor() as u64;
-            let mins: u64 = ((self.elapsed_time.as_secs() - hours * 3600) as f64 / 60.0).round() as u64;
-            if hours > 0 {
-                time_str.push_str(&hours.to_string());
-                time_str.push_str(" h");
-            }
-            time_str.push_str(" ");
-            time_str.push_str(&mins.to_string());
-            time_str.push_str(" min");
+        duration_to_string(self.elapsed_time)
+    }
+
+    pub fn add_time(&mut self, day: NaiveDate, time: Duration) {
+        self.elapsed_time += time;
+        *self.daily_time.entry(day).or_default() += time;
+        self.add_session(Utc::now(), time);
+    }
+
+    // Records time that ended now, as part of the last session if it was just running
+    fn add_session(&mut self, end: DateTime<Utc>, time: Duration) {
+        let start = end - chrono::Duration::from_std(time).unwrap_or_else(|_| chrono::Duration::zero());
+
+        match self.sessions.last_mut() {
+            Some(last) if start <= last.end + chrono::Duration::seconds(SESSION_GAP_SECS) => last.end = end,
+            _ => self.sessions.push(Session { start, end }),
         }
+    }
 
-        time_str
+    pub fn time_on(&self, day: NaiveDate) -> Duration {
+        self.daily_time.get(&day).copied().unwrap_or_default()
     }
 
     pub fn toggle_active(&mut self) {
-        if self.is_active {
-            self.is_active = false;
-        } else {
-            self.is_active = true;
+        self.is_active = !self.is_active;
+    }
+}
+
+pub fn duration_to_string(duration: Duration) -> String {
+    let mut time_str = String::from("");
+
+    if duration.as_secs() < 60 {
+        time_str.push_str("< 1 min");
+    } else {
+        let hours: u64 = (duration.as_secs() as f64 / 3600.0).floor() as u64;
+        let mins: u64 = ((duration.as_secs() - hours * 3600) as f64 / 60.0).round() as u64;
+        if hours > 0 {
+            time_str.push_str(&hours.to_string());
+            time_str.push_str(" h");
         }
+        time_str.push(' ');
+        time_str.push_str(&mins.to_string());
+        time_str.push_str(" min");
     }
-}
\ No newline at end of file
+
+    time_str
+}
+
+// A task created at a set time, for the tests of the modules that read, write
+// and merge tasks
+#[cfg(test)]
+pub fn test_task(title: &str) -> Task {
+    use chrono::TimeZone;
+
+    Task {
+        title: String::from(title),
+        created_on: Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap(),
+        ..Task::default()
+    }
+}
@@ -0,0 +1,160 @@
+// ----------------------------------------------------------------------------
+// THEME SUB-MODULE
+// This submodule defines colour themes: the built-in presets and the theme
+// files kept in the themes folder. A theme only holds the styles, so it can be
+// shared without the layout settings.
+// ----------------------------------------------------------------------------
+
+use crate::app::Settings;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use tui::style::{Color, Modifier, Style};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Theme {
+    // Taken from the file name for theme files
+    #[serde(skip)]
+    pub name: String,
+
+    pub default: Style,
+    pub highlight: Style,
+    pub active_normal: Style,
+    pub active_highlight: Style,
+    pub title: Style,
+    pub border: Style,
+}
+
+// Builds a preset from the colours of the settings page, in the order: main
+// fg and bg, selected fg and bg, active, title and border. Modifiers are for
+// main, selected, active, title and border text.
+fn preset(name: &str, colours: [Color; 7], modifiers: [Modifier; 5]) -> Theme {
+    let mut settings = Settings::default_settings();
+
+    settings.normal_fg_colour = colours[0];
+    settings.normal_bg_colour = colours[1];
+    settings.select_fg_colour = colours[2];
+    settings.select_bg_colour = colours[3];
+    settings.active_fg_colour = colours[4];
+    settings.title_fg_colour  = colours[5];
+    settings.border_colour    = colours[6];
+
+    settings.normal_modifiers = modifiers[0];
+    settings.select_modifiers = modifiers[1];
+    settings.active_modifiers = modifiers[2];
+    settings.title_modifiers  = modifiers[3];
+    settings.border_modifiers = modifiers[4];
+
+    settings.set_colours();
+    settings.to_theme(name)
+}
+
+pub fn builtin_themes() -> Vec<Theme> {
+    let plain = Modifier::empty();
+
+    vec![
+        preset(
+            "Default",
+            [Color::White, Color::Black, Color::Black, Color::White, Color::Green, Color::Green, Color::Green],
+            [plain, plain, plain, plain, plain],
+        ),
+        preset(
+            "Solarized dark",
+            [
+                Color::Rgb(0x83, 0x94, 0x96), Color::Rgb(0x00, 0x2b, 0x36),
+                Color::Rgb(0x93, 0xa1, 0xa1), Color::Rgb(0x07, 0x36, 0x42),
+                Color::Rgb(0x85, 0x99, 0x00), Color::Rgb(0xb5, 0x89, 0x00), Color::Rgb(0x26, 0x8b, 0xd2),
+            ],
+            [plain, plain, plain, Modifier::BOLD, plain],
+        ),
+        preset(
+            "Solarized light",
+            [
+                Color::Rgb(0x65, 0x7b, 0x83), Color::Rgb(0xfd, 0xf6, 0xe3),
+                Color::Rgb(0x58, 0x6e, 0x75), Color::Rgb(0xee, 0xe8, 0xd5),
+                Color::Rgb(0x85, 0x99, 0x00), Color::Rgb(0xcb, 0x4b, 0x16), Color::Rgb(0x26, 0x8b, 0xd2),
+            ],
+            [plain, plain, plain, Modifier::BOLD, plain],
+        ),
+        preset(
+            "Gruvbox",
+            [
+                Color::Rgb(0xeb, 0xdb, 0xb2), Color::Rgb(0x28, 0x28, 0x28),
+                Color::Rgb(0xfb, 0xf1, 0xc7), Color::Rgb(0x50, 0x49, 0x45),
+                Color::Rgb(0xb8, 0xbb, 0x26), Color::Rgb(0xfa, 0xbd, 0x2f), Color::Rgb(0x83, 0xa5, 0x98),
+            ],
+            [plain, plain, plain, Modifier::BOLD, plain],
+        ),
+        preset(
+            "High contrast",
+            [Color::White, Color::Black, Color::Black, Color::Yellow, Color::Green, Color::Yellow, Color::White],
+            [plain, Modifier::BOLD, Modifier::BOLD, Modifier::BOLD, plain],
+        ),
+        preset(
+            "Monochrome",
+            [Color::White, Color::Black, Color::Black, Color::White, Color::White, Color::White, Color::Gray],
+            [plain, plain, Modifier::BOLD, Modifier::UNDERLINED, plain],
+        ),
+    ]
+}
+
+// Theme files found in the themes folder, sorted by name. Files that can't be
+// read as a theme are skipped.
+pub fn file_themes(themes_dir: &Path) -> Vec<Theme> {
+    let mut themes = vec![];
+
+    if let Ok(entries) = fs::read_dir(themes_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let name = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+
+            if let Ok(content) = fs::read_to_string(&path) {
+                if let Ok(mut theme) = serde_json::from_str::<Theme>(&content) {
+                    theme.name = name;
+                    themes.push(theme);
+                }
+            }
+        }
+    }
+
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    themes
+}
+
+// All themes to choose from: the built-in presets followed by the theme files
+pub fn available_themes(themes_dir: &Path) -> Vec<Theme> {
+    let mut themes = builtin_themes();
+    themes.append(&mut file_themes(themes_dir));
+    themes
+}
+
+// Looks up a theme by name, ignoring case
+pub fn find_theme(themes_dir: &Path, name: &str) -> Option<Theme> {
+    available_themes(themes_dir)
+        .into_iter()
+        .find(|theme| theme.name.eq_ignore_ascii_case(name))
+}
+
+// Theme names become file names, so only allow a safe set of characters
+pub fn is_valid_theme_name(name: &str) -> bool {
+    !name.trim().is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_')
+}
+
+pub fn save_theme(themes_dir: &Path, theme: &Theme) -> io::Result<()> {
+    fs::create_dir_all(themes_dir)?;
+
+    let mut file_name = theme.name.clone();
+    file_name.push_str(".json");
+    fs::write(themes_dir.join(file_name), serde_json::to_vec_pretty(theme)?)
+}
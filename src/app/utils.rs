@@ -4,7 +4,9 @@
 // App module.
 // ----------------------------------------------------------------------------
 
-use tui::style::Color;
+use tui::{layout::Rect, style::{Color, Modifier}};
+
+use crossterm::event::KeyCode;
 
 // This function takes a Color from the TUI crate and returns the corresponding string to be shown.
 pub fn colour_to_string(colour: Color) -> String {
@@ -18,10 +20,42 @@ pub fn colour_to_string(colour: Color) -> String {
         Color::Gray     => String::from("Gray"),
         Color::DarkGray => String::from("Dark gray"),
         Color::Black    => String::from("Black"),
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Indexed(i)   => format!("Index {}", i),
         _               => String::from("Unknown"),
     }
 }
 
+// This function parses a colour typed in the settings: a name, #rrggbb or a 0-255 palette index.
+pub fn parse_colour(input: &str) -> Option<Color> {
+    let input = input.trim();
+
+    if let Some(hex) = input.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+        let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+        let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+        return Some(Color::Rgb(r, g, b));
+    }
+
+    if let Ok(index) = input.parse::<u8>() {
+        return Some(Color::Indexed(index));
+    }
+
+    let mut colour = Color::White;
+    loop {
+        if colour_to_string(colour).eq_ignore_ascii_case(input) {
+            return Some(colour);
+        }
+        colour = next_colour(colour);
+        if colour == Color::White {
+            return None;
+        }
+    }
+}
+
 pub fn next_colour(colour: Color) -> Color {
     match colour {
         Color::White    => Color::Cyan,
@@ -33,7 +67,7 @@ pub fn next_colour(colour: Color) -> Color {
         Color::Gray     => Color::DarkGray,
         Color::DarkGray => Color::Black,
         Color::Black    => Color::White,
-        _               => Color::Reset,
+        _               => Color::White,
     }
 }
 
@@ -48,6 +82,107 @@ pub fn prev_colour(colour: Color) -> Color {
         Color::Gray => Color::Yellow,
         Color::DarkGray => Color::Gray,
         Color::Black => Color::DarkGray,
-        _ => Color::Reset,
+        _ => Color::Black,
+    }
+}
+
+// This function takes a KeyCode from the crossterm crate and returns the label shown in the help.
+pub fn key_to_string(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => String::from("Space"),
+        KeyCode::Char(c)   => format!("'{}'", c),
+        KeyCode::F(n)      => format!("F{}", n),
+        KeyCode::Enter     => String::from("Enter"),
+        KeyCode::Esc       => String::from("Esc"),
+        KeyCode::Tab       => String::from("Tab"),
+        KeyCode::BackTab   => String::from("Shift+Tab"),
+        KeyCode::Backspace => String::from("Backspace"),
+        KeyCode::Up        => String::from("Up"),
+        KeyCode::Down      => String::from("Down"),
+        KeyCode::Left      => String::from("Left"),
+        KeyCode::Right     => String::from("Right"),
+        _                  => String::from("Unknown"),
+    }
+}
+
+// This function checks whether a screen position falls within a rectangle.
+pub fn rect_contains(rect: Rect, x: u16, y: u16) -> bool {
+    x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
+}
+
+// Autosave intervals in seconds the settings cycle through, 0 is off.
+pub const AUTOSAVE_STEPS: [u64; 7] = [0, 1, 2, 5, 10, 30, 60];
+pub const CHECKPOINT_STEPS: [u64; 7] = [0, 30, 60, 120, 300, 600, 1800];
+
+// This function returns the step after the value, going back to the first after the last.
+pub fn next_step(steps: &[u64], value: u64) -> u64 {
+    match steps.iter().position(|s| *s == value) {
+        Some(index) => steps[(index + 1) % steps.len()],
+        None        => steps.iter().copied().find(|s| *s > value).unwrap_or(steps[0]),
+    }
+}
+
+// This function returns the step before the value, going round to the last before the first.
+pub fn prev_step(steps: &[u64], value: u64) -> u64 {
+    match steps.iter().position(|s| *s == value) {
+        Some(index) => steps[(index + steps.len() - 1) % steps.len()],
+        None        => steps.iter().copied().rfind(|s| *s < value).unwrap_or(steps[steps.len() - 1]),
+    }
+}
+
+// This function returns the label of an interval in seconds.
+pub fn interval_to_string(secs: u64) -> String {
+    if secs == 0 {
+        String::from("Off")
+    } else if secs < 60 {
+        format!("{} s", secs)
+    } else {
+        format!("{} min", secs / 60)
+    }
+}
+
+// Text modifier combinations the settings cycle through, in order.
+const MODIFIER_CYCLE: [Modifier; 8] = [
+    Modifier::empty(),
+    Modifier::BOLD,
+    Modifier::ITALIC,
+    Modifier::UNDERLINED,
+    Modifier::from_bits_truncate(Modifier::BOLD.bits() | Modifier::ITALIC.bits()),
+    Modifier::from_bits_truncate(Modifier::BOLD.bits() | Modifier::UNDERLINED.bits()),
+    Modifier::from_bits_truncate(Modifier::ITALIC.bits() | Modifier::UNDERLINED.bits()),
+    Modifier::from_bits_truncate(Modifier::BOLD.bits() | Modifier::ITALIC.bits() | Modifier::UNDERLINED.bits()),
+];
+
+// This function takes text modifiers and returns the corresponding string to be shown.
+pub fn modifier_to_string(modifier: Modifier) -> String {
+    let mut names = vec![];
+    if modifier.contains(Modifier::BOLD) {
+        names.push("Bold");
+    }
+    if modifier.contains(Modifier::ITALIC) {
+        names.push("Italic");
+    }
+    if modifier.contains(Modifier::UNDERLINED) {
+        names.push("Underlined");
+    }
+
+    if names.is_empty() {
+        String::from("Plain")
+    } else {
+        names.join(", ")
+    }
+}
+
+pub fn next_modifier(modifier: Modifier) -> Modifier {
+    match MODIFIER_CYCLE.iter().position(|m| *m == modifier) {
+        Some(index) => MODIFIER_CYCLE[(index + 1) % MODIFIER_CYCLE.len()],
+        None        => Modifier::empty(),
+    }
+}
+
+pub fn prev_modifier(modifier: Modifier) -> Modifier {
+    match MODIFIER_CYCLE.iter().position(|m| *m == modifier) {
+        Some(index) => MODIFIER_CYCLE[(index + MODIFIER_CYCLE.len() - 1) % MODIFIER_CYCLE.len()],
+        None        => Modifier::empty(),
     }
 }
\ No newline at end of file
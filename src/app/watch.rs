@@ -0,0 +1,98 @@
+// ----------------------------------------------------------------------------
+// WATCH SUB-MODULE
+// This submodule keeps track of the data files on disk so that changes made
+// by other programs while the app is open can be noticed and reloaded.
+// ----------------------------------------------------------------------------
+
+use crate::app::ArchiveItem;
+use crate::app::task::Task;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+pub struct FileWatch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileWatch {
+    pub fn new(path: PathBuf) -> FileWatch {
+        let mut watch = FileWatch {
+            path,
+            modified: None,
+            len: 0,
+        };
+
+        watch.stamp();
+
+        watch
+    }
+
+    // Remember the file as it is now, to be called after the app reads or writes it
+    pub fn stamp(&mut self) {
+        match fs::metadata(&self.path) {
+            Ok(meta) => {
+                self.modified = meta.modified().ok();
+                self.len = meta.len();
+            },
+            Err(_) => {
+                self.modified = None;
+                self.len = 0;
+            },
+        }
+    }
+
+    // Whether the file was changed by someone else since the last stamp
+    pub fn changed(&self) -> bool {
+        match fs::metadata(&self.path) {
+            Ok(meta) => meta.modified().ok() != self.modified || meta.len() != self.len,
+            Err(_) => false,
+        }
+    }
+}
+
+// Compares task lists by what the user edits, leaving out the selection and
+// the timers, which change all the time while the app runs. Tasks are matched
+// up by id, so only edits count and not where the tasks are.
+pub fn same_tasks(a: &[Task], b: &[Task]) -> bool {
+    let b: HashMap<_, _> = b.iter().map(|task| (task.id, task)).collect();
+
+    a.len() == b.len() && a.iter().all(|x| {
+        b.get(&x.id).is_some_and(|y| {
+            x.title == y.title
+                && x.description == y.description
+                && x.is_done == y.is_done
+                && x.created_on == y.created_on
+                && x.priority == y.priority
+                && x.due == y.due
+        })
+    })
+}
+
+pub fn same_archive(a: &[ArchiveItem], b: &[ArchiveItem]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| {
+        x.date == y.date && same_tasks(&x.tasks, &y.tasks)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::task::test_task;
+
+    #[test]
+    fn tasks_are_matched_up_by_id() {
+        let tasks = vec![test_task("Write"), test_task("Read")];
+        let moved = vec![tasks[1].clone(), tasks[0].clone()];
+        let mut edited = tasks.clone();
+        edited[1].title = String::from("Read again");
+
+        assert!(same_tasks(&tasks, &moved));
+        assert!(!same_tasks(&tasks, &edited));
+        // The same titles with other ids are other tasks
+        assert!(!same_tasks(&tasks, &[test_task("Write"), test_task("Read")]));
+    }
+}
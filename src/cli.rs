@@ -0,0 +1,153 @@
+// ----------------------------------------------------------------------------
+// CLI MODULE
+// This module defines the command line: the options for starting the app and
+// the subcommands that run without it. Shell completions and the man page are
+// generated from the same definition.
+// ----------------------------------------------------------------------------
+
+use crate::app::formats::{Format, TimeFormat};
+use crate::app::merge::Prefer;
+
+use std::io;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+
+#[derive(Parser)]
+#[command(name = "todo_rust", version, about = "A terminal to-do list with time tracking")]
+pub struct Cli {
+    /// Folder holding tasks, settings and themes, same as TODO_RUST_DIR
+    #[arg(value_name = "FOLDER")]
+    pub folder: Option<String>,
+
+    /// Folder for tasks and archive [default: $XDG_DATA_HOME/todo_rust]
+    #[arg(short, long, value_name = "DIR", global = true)]
+    pub data_dir: Option<String>,
+
+    /// Folder for settings and themes [default: $XDG_CONFIG_HOME/todo_rust]
+    #[arg(short, long, value_name = "DIR", global = true)]
+    pub config: Option<String>,
+
+    /// Show the tasks without allowing any change
+    #[arg(short, long)]
+    pub read_only: bool,
+
+    /// Colour theme to start with, built-in or from the themes folder
+    #[arg(short, long, value_name = "NAME")]
+    pub theme: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print shell completions
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page
+    Man,
+    /// Write the tasks and the archive in the format of another tool
+    Export {
+        #[arg(value_enum)]
+        format: Format,
+        /// File to write, stdout if not given
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Leave out archive batches from before this day, and tasks created before it
+        #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+        from: Option<NaiveDate>,
+        /// Leave out archive batches from after this day, and tasks created after it
+        #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+        to: Option<NaiveDate>,
+        /// Column delimiter for CSV, a character or "tab"
+        #[arg(long, value_name = "CHAR", default_value = ",", value_parser = parse_delimiter)]
+        delimiter: u8,
+        /// How CSV shows the tracked hours
+        #[arg(long, value_enum, default_value = "decimal")]
+        time_format: TimeFormat,
+    },
+    /// Add the tasks of a file in the format of another tool
+    Import {
+        #[arg(value_enum)]
+        format: Format,
+        /// File to read, stdin if not given
+        #[arg(value_name = "FILE")]
+        input: Option<PathBuf>,
+    },
+    /// Merge two copies of tasks.json, archive.json or a data folder changed apart from a common one
+    Merge {
+        /// The copy both were changed from
+        #[arg(value_name = "BASE")]
+        base: PathBuf,
+        /// Our copy, the result is written to it unless --output is given
+        #[arg(value_name = "OURS")]
+        ours: PathBuf,
+        /// Their copy
+        #[arg(value_name = "THEIRS")]
+        theirs: PathBuf,
+        /// File or folder to write the result to, "-" for stdout
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Side to take for fields changed on both, without it conflicts keep
+        /// ours and the command fails, as git expects of merge drivers
+        #[arg(long, value_enum)]
+        prefer: Option<Prefer>,
+    },
+    /// Serve the tasks over a local HTTP API with JSON bodies
+    Serve {
+        /// Address to listen on
+        #[arg(long, value_name = "HOST", default_value = "127.0.0.1")]
+        host: String,
+        /// Port to listen on
+        #[arg(short, long, value_name = "PORT", default_value_t = 7270)]
+        port: u16,
+    },
+    /// Send a command to the app running on the data folder: toggle-active,
+    /// add <title>, done-selected or status
+    Send {
+        #[arg(value_name = "COMMAND", required = true)]
+        command: Vec<String>,
+    },
+    /// Commit the tasks to the git repository of the data folder, pull and push
+    Sync {
+        #[command(subcommand)]
+        action: Option<SyncCommand>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SyncCommand {
+    /// Keep the data folder in a git repository, or change its remote
+    Init {
+        /// Repository to pull from and push to, e.g. a bare repository
+        #[arg(value_name = "REMOTE")]
+        remote: Option<String>,
+    },
+}
+
+fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").map_err(|_| String::from("expected a date like 2024-03-01"))
+}
+
+fn parse_delimiter(text: &str) -> Result<u8, String> {
+    match text {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if text.len() == 1 && text.is_ascii() => Ok(text.as_bytes()[0]),
+        _ => Err(String::from("expected a single character or \"tab\"")),
+    }
+}
+
+pub fn print_completions(shell: Shell) {
+    let mut command = Cli::command();
+    clap_complete::generate(shell, &mut command, "todo_rust", &mut io::stdout());
+}
+
+pub fn print_man() -> io::Result<()> {
+    clap_mangen::Man::new(Cli::command()).render(&mut io::stdout())
+}
@@ -0,0 +1,74 @@
+// ----------------------------------------------------------------------------
+// ERROR MODULE
+// This module defines the errors of the app, so callers can tell a file that
+// can't be read from one that can't be parsed or a terminal that went away.
+// ----------------------------------------------------------------------------
+
+use std::fmt;
+use std::io;
+use std::sync::mpsc::RecvError;
+
+#[derive(Debug)]
+pub enum Error {
+    // Reading or writing files
+    Io(io::Error),
+    // Files that aren't valid JSON or don't match the data
+    Parse(serde_json::Error),
+    // Data files that can't be used as they are, e.g. from a newer version
+    Storage(String),
+    // Drawing or reading input
+    Terminal(String),
+    // Command line options that can't be used
+    Usage(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err)       => write!(f, "{}", err),
+            Error::Parse(err)    => write!(f, "{}", err),
+            Error::Storage(msg)  => write!(f, "{}", msg),
+            Error::Terminal(msg) => write!(f, "terminal error: {}", msg),
+            Error::Usage(msg)    => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err)    => Some(err),
+            Error::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Parse(err)
+    }
+}
+
+impl From<crossterm::ErrorKind> for Error {
+    fn from(err: crossterm::ErrorKind) -> Error {
+        match err {
+            crossterm::ErrorKind::IoError(err) => Error::Io(err),
+            err => Error::Terminal(err.to_string()),
+        }
+    }
+}
+
+impl From<RecvError> for Error {
+    fn from(_: RecvError) -> Error {
+        Error::Terminal(String::from("input events stopped"))
+    }
+}
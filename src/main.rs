@@ -1,9 +1,29 @@
 mod app;
+mod cli;
+mod error;
+mod paths;
 
 use crate::app::App;
+use crate::cli::{Cli, Command, SyncCommand};
+use crate::error::{Error, Result};
+use crate::paths::Paths;
 
-use std::env;
+use clap::Parser;
+use crate::app::formats::{self, ExportOptions};
+use crate::app::lock::{self, DataLock, holder};
+use crate::app::schema::InvalidFile;
+use crate::app::merge::{self, Prefer};
+#[cfg(unix)]
+use crate::app::control;
+use crate::app::server;
+use crate::app::sync;
+
+use std::fs;
 use std::io;
+use std::io::Write;
+use std::panic;
+use std::path::Path;
+use std::process;
 
 use tui::{
     backend::CrosstermBackend,
@@ -11,55 +31,278 @@ use tui::{
 };
 
 use crossterm::{
+    cursor::Show,
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
 // ---- MAIN FUNCTION ----
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("todo_rust: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    // ---- PARSE INPUT ARGUMENTS AND CREATE APP ----
+    // Bad arguments print the usage and exit before anything is touched
+    let cli = Cli::parse();
+    let mut read_only = cli.read_only;
+
+    match cli.command {
+        Some(Command::Completions { shell }) => {
+            cli::print_completions(shell);
+            return Ok(());
+        },
+        Some(Command::Man) => {
+            cli::print_man()?;
+            return Ok(());
+        },
+        Some(Command::Merge { base, ours, theirs, output, prefer }) => {
+            let conflicts = merge::merge_paths(&base, &ours, &theirs, output.as_deref(), prefer.unwrap_or(Prefer::Ours))?;
+            for conflict in &conflicts {
+                eprintln!("conflict: {}", conflict);
+            }
+            if prefer.is_none() && !conflicts.is_empty() {
+                let count = conflicts.len();
+                return Err(Error::Storage(format!("{} conflict{} kept ours", count, if count == 1 { "" } else { "s" })));
+            }
+            return Ok(());
+        },
+        _ => {},
+    }
+
+    let paths = Paths::resolve(cli.folder.as_deref(), cli.data_dir.as_deref(), cli.config.as_deref())?;
+
+    // ---- RUN COMMANDS ON THE FILES ----
+    match cli.command {
+        Some(Command::Export { format, output, from, to, delimiter, time_format }) => {
+            let options = ExportOptions { from, to, delimiter, time_format };
+            return formats::export(&paths.data_dir, format, output.as_deref(), &options);
+        },
+        Some(Command::Import { format, input }) => {
+            paths.create()?;
+            let _lock = lock_for_command(&paths.data_dir, "importing")?;
+            let count = formats::import(&paths.data_dir, format, input.as_deref())?;
+            if sync::is_enabled(&paths.data_dir) {
+                sync::commit(&paths.data_dir)?;
+            }
+            println!("Imported {} task{}", count, if count == 1 { "" } else { "s" });
+            return Ok(());
+        },
+        Some(Command::Serve { host, port }) => {
+            paths.create()?;
+            let _lock = lock_for_command(&paths.data_dir, "serving them")?;
+            // IPv6 addresses go in brackets before the port
+            let address = if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) };
+            return server::serve(&paths.data_dir, &address);
+        },
+        Some(Command::Send { command }) => {
+            return send_command(&paths.data_dir, &command.join(" "));
+        },
+        Some(Command::Sync { action }) => {
+            paths.create()?;
+            let _lock = lock_for_command(&paths.data_dir, "syncing")?;
+            match action {
+                Some(SyncCommand::Init { remote }) => sync::init(&paths.data_dir, remote.as_deref())?,
+                None if sync::is_enabled(&paths.data_dir) => sync::sync(&paths.data_dir)?,
+                None => return Err(Error::Usage(String::from("the data folder isn't synced, set it up with todo_rust sync init [REMOTE]"))),
+            }
+            return Ok(());
+        },
+        _ => {},
+    }
+    if !read_only {
+        paths.create()?;
+    }
+
+    // ---- LOCK DATA FOLDER ----
+    // Held until the end of main, so it is also released when a panic unwinds.
+    // Read-only instances don't need it, they never write.
+    let data_dir = paths.data_dir.as_path();
+    let _lock = if read_only {
+        None
+    } else {
+        match DataLock::acquire(data_dir) {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                if !ask_read_only(data_dir)? {
+                    return Ok(());
+                }
+                read_only = true;
+                None
+            },
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                read_only = true;
+                None
+            },
+            Err(err) => return Err(err.into()),
+        }
+    };
+
+    // Files that can't be written are only shown
+    if ["tasks.json", "archive.json"].iter().any(|f| is_read_only(&data_dir.join(f))) {
+        read_only = true;
+    }
+
+    // ---- SYNC DATA FOLDER ----
+    // The changes of other machines are taken in before the files are loaded.
+    // Failing to reach the remote shouldn't keep the tasks from being used.
+    let git_sync = !read_only && sync::is_enabled(data_dir);
+    if git_sync {
+        if let Err(err) = sync::sync(data_dir) {
+            eprintln!("todo_rust: could not sync: {}", err);
+        }
+    }
+
+    // ---- CHECK DATA FILES ----
+    // Done before the terminal is taken over so the questions can be answered
+    loop {
+        let invalid_files = App::check_files(&paths);
+        if invalid_files.is_empty() {
+            break;
+        }
+
+        for invalid in &invalid_files {
+            if !ask_recovery(invalid, read_only)? {
+                return Ok(());
+            }
+        }
+    }
+
+    let mut app = App::new(&paths, read_only)?;
+    if let Some(theme) = &cli.theme {
+        app.use_theme(theme)?;
+    }
+
     // ---- SET UP TERMINAL ----
+    // The terminal is restored by the guard on return and by the hook on a
+    // panic, before the panic message is printed so it can be read
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+
     enable_raw_mode()?;
+    let _guard = TerminalGuard;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // ---- PARSE INPUT ARGUMENTS AND CREATE APP ----
-    let in_args: Vec<String> = env::args().collect();
-    let num_args = in_args.len();
-    let path_to_folder: String;
-
-    if num_args > 3 {
-        println!("");
-        println!("Too many arguments supplied! Either:");
-        println!("  - Run the program with no args: this will create a local database file (tasks.json)");
-        println!("  - Provide the file to be used as the first argument");
-        println!("");
-        panic!();
-    } else if num_args == 1 {
-        path_to_folder = String::from("./");
-    } else {
-        path_to_folder = in_args[1].clone();
+    // ---- RUN APP ----
+    app.run(&mut terminal)?;
+
+    // The terminal is given back first, pushing can take a while
+    if git_sync {
+        restore_terminal();
+        if let Err(err) = sync::sync(data_dir) {
+            eprintln!("todo_rust: could not sync: {}", err);
+        }
     }
 
-    let mut app = App::new(&path_to_folder)?;
+    Ok(())
+}
+
+// Restores the terminal when dropped, whichever way main is left
+struct TerminalGuard;
 
-    // ---- RUN APP ----
-    let res = app.run(&mut terminal);
-
-    // ---- RESTORE TERMINAL ----
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    if let Err(err) = res {
-        println!("{:?}", err)
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+// Puts the terminal back as it was found, it is fine to do it more than once
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+}
+
+// Commands that change the files can't run while the app has them open
+fn lock_for_command(data_dir: &Path, doing: &str) -> Result<DataLock> {
+    match DataLock::acquire(data_dir)? {
+        Some(lock) => Ok(lock),
+        None => {
+            let pid = holder(data_dir).map(|p| p.to_string()).unwrap_or_else(|| String::from("?"));
+            Err(Error::Storage(format!("the tasks in {} are open in todo_rust (pid {}), close it before {}", data_dir.display(), pid, doing)))
+        },
+    }
+}
+
+// Sends a command to the app running on the data folder and prints its answer
+#[cfg(unix)]
+fn send_command(data_dir: &Path, command: &str) -> Result<()> {
+    let answer = control::send(data_dir, command)?;
+    if let Some(message) = answer.strip_prefix("error: ") {
+        return Err(Error::Usage(message.to_string()));
     }
 
+    println!("{}", answer);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(not(unix))]
+fn send_command(_: &Path, _: &str) -> Result<()> {
+    Err(Error::Usage(String::from("commands can only be sent to the app on Unix")))
+}
+
+// Asks whether to open the data folder read-only when another instance has it open
+fn ask_read_only(data_dir: &Path) -> io::Result<bool> {
+    let pid = holder(data_dir).map(|p| p.to_string()).unwrap_or_else(|| String::from("?"));
+    println!("The tasks in {} are open in another todo_rust (pid {}), which holds a lock on {}.", data_dir.display(), pid, lock::lock_path(data_dir).display());
+    print!("Open them read-only? [Y/n] ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(!answer.trim().to_lowercase().starts_with('n'))
+}
+
+// Asks what to do with a data file that can't be loaded, returns false to quit
+fn ask_recovery(invalid: &InvalidFile, read_only: bool) -> io::Result<bool> {
+    println!("Could not load {}: {}", invalid.path.display(), invalid.error);
+
+    if read_only {
+        println!("Fix or restore the file, it can't be changed in read-only mode.");
+        return Ok(false);
+    }
+
+    let backup_name = invalid.backup.as_ref().and_then(|b| b.file_name()).map(|n| n.to_string_lossy().to_string());
+    if let Some(name) = &backup_name {
+        println!("  [r] Restore the latest backup ({})", name);
+    }
+    println!("  [e] Start with an empty file, the broken one is kept as {}.corrupt", invalid.path.file_name().unwrap_or_default().to_string_lossy());
+    println!("  [q] Quit");
+
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(false);
+        }
+
+        match answer.trim() {
+            "r" if backup_name.is_some() => {
+                invalid.restore_backup()?;
+                return Ok(true);
+            },
+            "e" => {
+                invalid.set_aside()?;
+                return Ok(true);
+            },
+            "q" => return Ok(false),
+            _ => {},
+        }
+    }
+}
+
+fn is_read_only(path: &Path) -> bool {
+    fs::metadata(path).map(|m| m.permissions().readonly()).unwrap_or(false)
+}
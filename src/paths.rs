@@ -0,0 +1,70 @@
+// ----------------------------------------------------------------------------
+// PATHS MODULE
+// This module works out where the app keeps its files. Tasks go in the XDG
+// data folder and settings and themes in the XDG config folder, unless a
+// folder is given on the command line or in TODO_RUST_DIR, which then holds
+// everything. Either folder can also be given on its own.
+// ----------------------------------------------------------------------------
+
+use crate::error::{Error, Result};
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// ---- CONSTANTS ----
+const APP_DIR: &str = "todo_rust";
+const DIR_VAR: &str = "TODO_RUST_DIR";
+
+pub struct Paths {
+    // Tasks, archive and lock file
+    pub data_dir: PathBuf,
+    // Settings and themes
+    pub config_dir: PathBuf,
+}
+
+impl Paths {
+    // The separate folders win over the folder argument, which wins over the
+    // environment variable, which wins over the XDG folders
+    pub fn resolve(folder_arg: Option<&str>, data_arg: Option<&str>, config_arg: Option<&str>) -> Result<Paths> {
+        let folder = folder_arg
+            .map(PathBuf::from)
+            .or_else(|| env::var_os(DIR_VAR).filter(|dir| !dir.is_empty()).map(PathBuf::from));
+
+        let data_dir = match (data_arg, &folder) {
+            (Some(dir), _) => PathBuf::from(dir),
+            (None, Some(folder)) => folder.clone(),
+            (None, None) => xdg_dir("XDG_DATA_HOME", ".local/share")?.join(APP_DIR),
+        };
+
+        let config_dir = match (config_arg, &folder) {
+            (Some(dir), _) => PathBuf::from(dir),
+            (None, Some(folder)) => folder.clone(),
+            (None, None) => xdg_dir("XDG_CONFIG_HOME", ".config")?.join(APP_DIR),
+        };
+
+        Ok(Paths { data_dir, config_dir })
+    }
+
+    pub fn create(&self) -> Result<()> {
+        fs::create_dir_all(&self.data_dir)?;
+        fs::create_dir_all(&self.config_dir)?;
+
+        Ok(())
+    }
+}
+
+// The base folder from the variable, or the fallback in the home folder. The
+// spec says relative paths in the variables are to be ignored.
+fn xdg_dir(var: &str, fallback: &str) -> Result<PathBuf> {
+    if let Some(dir) = env::var_os(var).map(PathBuf::from) {
+        if dir.is_absolute() {
+            return Ok(dir);
+        }
+    }
+
+    match env::var_os("HOME").filter(|home| !home.is_empty()) {
+        Some(home) => Ok(PathBuf::from(home).join(fallback)),
+        None => Err(Error::Storage(format!("can't find a folder for the tasks: set {} or HOME, or give a folder", var))),
+    }
+}